@@ -0,0 +1,130 @@
+//! Arithmetic and logic operations of the instruction set.
+//!
+//! Each function computes an instruction's result and the STATUS flags it
+//! affects, following the mid-range instruction set reference. Flags an
+//! instruction leaves alone are `None`.
+
+/// Result of an ALU operation together with the flags it updates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub result: u8,
+    pub z: Option<bool>,
+    pub dc: Option<bool>,
+    pub c: Option<bool>,
+}
+
+impl Outcome {
+    /// A result that affects no flags.
+    fn plain(result: u8) -> Self {
+        Self {
+            result,
+            z: None,
+            dc: None,
+            c: None,
+        }
+    }
+
+    /// A result that affects only Z.
+    fn zero(result: u8) -> Self {
+        Self {
+            z: Some(result == 0),
+            ..Self::plain(result)
+        }
+    }
+}
+
+/// `ADDWF`/`ADDLW`: `a + b`. C and DC are the carries out of bits 7 and 3.
+pub fn add(a: u8, b: u8) -> Outcome {
+    let (result, c) = a.overflowing_add(b);
+    Outcome {
+        dc: Some((a & 0x0F) + (b & 0x0F) > 0x0F),
+        c: Some(c),
+        ..Outcome::zero(result)
+    }
+}
+
+/// `SUBWF`/`SUBLW`: `a - b`. C and DC are the complements of the borrows out
+/// of bits 7 and 3, so C is set when `a >= b`.
+pub fn sub(a: u8, b: u8) -> Outcome {
+    Outcome {
+        dc: Some(a & 0x0F >= b & 0x0F),
+        c: Some(a >= b),
+        ..Outcome::zero(a.wrapping_sub(b))
+    }
+}
+
+/// `ANDWF`/`ANDLW`.
+pub fn and(a: u8, b: u8) -> Outcome {
+    Outcome::zero(a & b)
+}
+
+/// `IORWF`/`IORLW`.
+pub fn or(a: u8, b: u8) -> Outcome {
+    Outcome::zero(a | b)
+}
+
+/// `XORWF`/`XORLW`.
+pub fn xor(a: u8, b: u8) -> Outcome {
+    Outcome::zero(a ^ b)
+}
+
+/// `CLRF`/`CLRW`.
+pub fn clear() -> Outcome {
+    Outcome::zero(0)
+}
+
+/// `COMF`.
+pub fn complement(f: u8) -> Outcome {
+    Outcome::zero(!f)
+}
+
+/// `MOVF`: passes `f` through, setting Z so `movf f,f` tests a register.
+pub fn mov(f: u8) -> Outcome {
+    Outcome::zero(f)
+}
+
+/// `DECF`, wrapping from 0 to 255.
+pub fn decrement(f: u8) -> Outcome {
+    Outcome::zero(f.wrapping_sub(1))
+}
+
+/// `INCF`, wrapping from 255 to 0.
+pub fn increment(f: u8) -> Outcome {
+    Outcome::zero(f.wrapping_add(1))
+}
+
+/// `DECFSZ`: no flags change; the caller skips when the result is zero.
+pub fn decrement_skip(f: u8) -> Outcome {
+    Outcome::plain(f.wrapping_sub(1))
+}
+
+/// `INCFSZ`: no flags change; the caller skips when the result is zero.
+pub fn increment_skip(f: u8) -> Outcome {
+    Outcome::plain(f.wrapping_add(1))
+}
+
+/// `RLF`: rotates left through carry.
+pub fn rotate_left(f: u8, carry: bool) -> Outcome {
+    Outcome {
+        c: Some(f & 0x80 != 0),
+        ..Outcome::plain((f << 1) | carry as u8)
+    }
+}
+
+/// `RRF`: rotates right through carry.
+pub fn rotate_right(f: u8, carry: bool) -> Outcome {
+    Outcome {
+        c: Some(f & 0x01 != 0),
+        ..Outcome::plain((f >> 1) | ((carry as u8) << 7))
+    }
+}
+
+/// `SWAPF`: exchanges the nibbles without touching any flag.
+pub fn swap(f: u8) -> Outcome {
+    Outcome::plain(f.rotate_left(4))
+}
+
+/// Whether `bit` of `f` is set, as tested by `BTFSC`/`BTFSS`.
+pub fn bit_set(f: u8, bit: u8) -> bool {
+    f & (1 << bit) != 0
+}
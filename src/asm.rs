@@ -0,0 +1,1108 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    exec::{Instruction, decode},
+    image::{CONFIG_ADDRESS, EEPROM_ADDRESS, EEPROM_SIZE, ID_ADDRESS, Image, PROGRAM_SIZE},
+};
+
+/// Symbols defined by `#include p16core.inc`.
+const P16CORE_INC: &[(&str, i64)] = &[
+    ("W", 0),
+    ("F", 1),
+    // Registers
+    ("INDF", 0x00),
+    ("TMR0", 0x01),
+    ("PCL", 0x02),
+    ("STATUS", 0x03),
+    ("FSR", 0x04),
+    ("PORTA", 0x05),
+    ("PORTB", 0x06),
+    ("PORTC", 0x07),
+    ("PORTD", 0x08),
+    ("PCLATH", 0x0A),
+    ("INTCON", 0x0B),
+    ("PIR1", 0x0C),
+    ("INDF1", 0x0E),
+    ("INDF2", 0x0F),
+    ("T1CON", 0x10),
+    ("T1L", 0x11),
+    ("T1H", 0x12),
+    ("DAN", 0x13),
+    ("DSEG", 0x14),
+    ("RCSTA", 0x18),
+    ("TXREG", 0x19),
+    ("RCREG", 0x1A),
+    ("PTR1L", 0x1C),
+    ("PTR1H", 0x1D),
+    ("PTR2L", 0x1E),
+    ("PTR2H", 0x1F),
+    ("OPTION_REG", 0x81),
+    ("TRISA", 0x85),
+    ("TRISB", 0x86),
+    ("TRISC", 0x87),
+    ("TRISD", 0x88),
+    ("PIE1", 0x8C),
+    // STATUS bits
+    ("IRP", 7),
+    ("RP1", 6),
+    ("RP0", 5),
+    ("NOT_TO", 4),
+    ("NOT_PD", 3),
+    ("Z", 2),
+    ("DC", 1),
+    ("C", 0),
+    // INTCON bits
+    ("GIE", 7),
+    ("PEIE", 6),
+    ("T0IE", 5),
+    ("TMR0IE", 5),
+    ("INTE", 4),
+    ("RBIE", 3),
+    ("T0IF", 2),
+    ("TMR0IF", 2),
+    ("INTF", 1),
+    ("RBIF", 0),
+    // OPTION_REG bits
+    ("NOT_RBPU", 7),
+    ("INTEDG", 6),
+    ("T0CS", 5),
+    ("T0SE", 4),
+    ("PSA", 3),
+    ("PS2", 2),
+    ("PS1", 1),
+    ("PS0", 0),
+    // PIR1 bits
+    ("PSPIF", 7),
+    ("ADIF", 6),
+    ("RCIF", 5),
+    ("TXIF", 4),
+    ("SSPIF", 3),
+    ("CCP1IF", 2),
+    ("TMR2IF", 1),
+    ("TMR1IF", 0),
+    // PIE1 bits
+    ("PSPIE", 7),
+    ("ADIE", 6),
+    ("RCIE", 5),
+    ("TXIE", 4),
+    ("SSPIE", 3),
+    ("CCP1IE", 2),
+    ("TMR2IE", 1),
+    ("TMR1IE", 0),
+    // T1CON bits
+    ("T1CKPS1", 5),
+    ("T1CKPS0", 4),
+    ("T1OSCEN", 3),
+    ("NOT_T1SYNC", 2),
+    ("TMR1CS", 1),
+    ("TMR1ON", 0),
+    // RCSTA bits
+    ("SPEN", 7),
+    ("RX9", 6),
+    ("SREN", 5),
+    ("CREN", 4),
+    ("ADDEN", 3),
+    ("FERR", 2),
+    ("OERR", 1),
+    ("RX9D", 0),
+    // Configuration bits, combined with `&` in `__config`
+    ("_CP_ON", 0x1FFF),
+    ("_CP_OFF", 0x3FFF),
+    ("_DEBUG_ON", 0x37FF),
+    ("_DEBUG_OFF", 0x3FFF),
+    ("_CPD_ON", 0x3EFF),
+    ("_CPD_OFF", 0x3FFF),
+    ("_LVP_ON", 0x3FFF),
+    ("_LVP_OFF", 0x3F7F),
+    ("_BOREN_ON", 0x3FFF),
+    ("_BOREN_OFF", 0x3FBF),
+    ("_PWRTE_ON", 0x3FF7),
+    ("_PWRTE_OFF", 0x3FFF),
+    ("_WDT_ON", 0x3FFF),
+    ("_WDT_OFF", 0x3FFB),
+    ("_LP_OSC", 0x3FFC),
+    ("_XT_OSC", 0x3FFD),
+    ("_HS_OSC", 0x3FFE),
+    ("_RC_OSC", 0x3FFF),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AsmError {
+    UnknownMnemonic {
+        line: usize,
+        mnemonic: String,
+    },
+    UnknownInclude {
+        line: usize,
+        file: String,
+    },
+    UndefinedSymbol {
+        line: usize,
+        name: String,
+    },
+    DuplicateSymbol {
+        line: usize,
+        name: String,
+    },
+    /// An operand or expression could not be parsed.
+    InvalidOperand {
+        line: usize,
+        operand: String,
+    },
+    WrongOperandCount {
+        line: usize,
+        expected: usize,
+    },
+    /// A value does not fit the field it is assembled into.
+    OutOfRange {
+        line: usize,
+        value: i64,
+    },
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AsmError::UnknownMnemonic { line, mnemonic } => {
+                write!(f, "line {line}: unknown mnemonic or directive `{mnemonic}`")
+            }
+            AsmError::UnknownInclude { line, file } => {
+                write!(f, "line {line}: cannot include `{file}`")
+            }
+            AsmError::UndefinedSymbol { line, name } => {
+                write!(f, "line {line}: undefined symbol `{name}`")
+            }
+            AsmError::DuplicateSymbol { line, name } => {
+                write!(f, "line {line}: symbol `{name}` is already defined")
+            }
+            AsmError::InvalidOperand { line, operand } => {
+                write!(f, "line {line}: invalid operand `{operand}`")
+            }
+            AsmError::WrongOperandCount { line, expected } => {
+                write!(f, "line {line}: expected {expected} operand(s)")
+            }
+            AsmError::OutOfRange { line, value } => {
+                write!(f, "line {line}: value 0x{value:X} is out of range")
+            }
+        }
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// Output of [`assemble`].
+#[derive(Debug, Clone)]
+pub struct Assembly {
+    pub image: Image,
+    /// Every assembled instruction with its program address, in source order.
+    pub instructions: Vec<(u16, Instruction)>,
+    /// Labels, RAM variables and `equ` constants, including the include file.
+    pub symbols: HashMap<String, i64>,
+}
+
+/// Assembles MPASM-style source for the p16core into a program image.
+///
+/// Supports labels, `org`, `udata`/`res`, `cblock`/`endc`, `equ`/`set`,
+/// `#define`, `dw`, `de`, `__config`, `__idlocs`, `banksel`, `radix`, `end`
+/// and `#include p16core.inc` for the register and bit names. Literals may be
+/// written as `0x1F`, `H'1F'`, `D'31'`, `.31`, `B'11111'`, `O'37'` or
+/// `'A'`; bare numbers use the current radix, hex by default.
+pub fn assemble(source: &str) -> Result<Assembly, AsmError> {
+    let lines = source
+        .lines()
+        .enumerate()
+        .map(|(index, text)| SourceLine::parse(index + 1, text))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut asm = Assembler::default();
+    asm.pass(&lines, Pass::Symbols)?;
+    asm.pass(&lines, Pass::Emit)?;
+
+    Ok(Assembly {
+        image: asm.image,
+        instructions: asm.instructions,
+        symbols: asm.symbols,
+    })
+}
+
+#[derive(Debug)]
+struct SourceLine<'a> {
+    number: usize,
+    /// The whole statement without its comment.
+    text: &'a str,
+    label: Option<&'a str>,
+    op: Option<&'a str>,
+    operands: Vec<&'a str>,
+}
+
+impl<'a> SourceLine<'a> {
+    fn parse(number: usize, text: &'a str) -> Result<Self, AsmError> {
+        let text = strip_comment(text);
+        let starts_in_column_one = text.starts_with(|c: char| !c.is_whitespace());
+        let mut rest = text.trim();
+
+        let mut label = None;
+        if starts_in_column_one && !rest.starts_with('#') {
+            let end = rest
+                .find(|c: char| c.is_whitespace() || c == ':')
+                .unwrap_or(rest.len());
+            let name = &rest[..end];
+            if !is_directive(name) && !is_mnemonic(name) {
+                label = Some(name);
+                rest = rest[end..].trim_start_matches(':').trim();
+            }
+        } else if let Some(end) = rest.find(':') {
+            // An indented label must be followed by a colon.
+            let name = &rest[..end];
+            if is_identifier(name) {
+                label = Some(name);
+                rest = rest[end + 1..].trim();
+            }
+        }
+
+        let (op, operands) = if rest.is_empty() {
+            (None, Vec::new())
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let operands = split_operands(rest[end..].trim(), number)?;
+            (Some(&rest[..end]), operands)
+        };
+
+        Ok(Self {
+            number,
+            text: text.trim(),
+            label,
+            op,
+            operands,
+        })
+    }
+}
+
+fn strip_comment(text: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            '\'' | '"' => quoted = !quoted,
+            ';' if !quoted => return &text[..i],
+            _ => {}
+        }
+    }
+    text
+}
+
+fn split_operands(text: &str, line: usize) -> Result<Vec<&str>, AsmError> {
+    if text.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut operands = Vec::new();
+    let mut depth = 0i32;
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '\'' | '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            ',' if !quoted && depth == 0 => {
+                operands.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    operands.push(text[start..].trim());
+    if operands.iter().any(|operand| operand.is_empty()) || quoted || depth != 0 {
+        return Err(AsmError::InvalidOperand {
+            line,
+            operand: text.to_string(),
+        });
+    }
+    Ok(operands)
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+const DIRECTIVES: &[&str] = &[
+    "#include",
+    "#define",
+    "include",
+    "list",
+    "nolist",
+    "processor",
+    "radix",
+    "errorlevel",
+    "udata",
+    "udata_shr",
+    "res",
+    "org",
+    "code",
+    "end",
+    "equ",
+    "set",
+    "constant",
+    "cblock",
+    "endc",
+    "dw",
+    "data",
+    "de",
+    "__config",
+    "__idlocs",
+    "banksel",
+];
+
+fn is_directive(name: &str) -> bool {
+    DIRECTIVES.contains(&name.to_ascii_lowercase().as_str())
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Operands {
+    None,
+    File,
+    FileDest,
+    FileBit,
+    Literal,
+    Address,
+}
+
+/// Mnemonic, base opcode and operand format of every instruction.
+const OPCODES: &[(&str, u16, Operands)] = &[
+    ("addwf", 0x0700, Operands::FileDest),
+    ("andwf", 0x0500, Operands::FileDest),
+    ("clrf", 0x0180, Operands::File),
+    ("clrw", 0x0100, Operands::None),
+    ("comf", 0x0900, Operands::FileDest),
+    ("decf", 0x0300, Operands::FileDest),
+    ("decfsz", 0x0B00, Operands::FileDest),
+    ("incf", 0x0A00, Operands::FileDest),
+    ("incfsz", 0x0F00, Operands::FileDest),
+    ("iorwf", 0x0400, Operands::FileDest),
+    ("movf", 0x0800, Operands::FileDest),
+    ("movwf", 0x0080, Operands::File),
+    ("nop", 0x0000, Operands::None),
+    ("rlf", 0x0D00, Operands::FileDest),
+    ("rrf", 0x0C00, Operands::FileDest),
+    ("subwf", 0x0200, Operands::FileDest),
+    ("swapf", 0x0E00, Operands::FileDest),
+    ("xorwf", 0x0600, Operands::FileDest),
+    ("bcf", 0x1000, Operands::FileBit),
+    ("bsf", 0x1400, Operands::FileBit),
+    ("btfsc", 0x1800, Operands::FileBit),
+    ("btfss", 0x1C00, Operands::FileBit),
+    ("addlw", 0x3E00, Operands::Literal),
+    ("andlw", 0x3900, Operands::Literal),
+    ("call", 0x2000, Operands::Address),
+    ("clrwdt", 0x0064, Operands::None),
+    ("goto", 0x2800, Operands::Address),
+    ("iorlw", 0x3800, Operands::Literal),
+    ("movlw", 0x3000, Operands::Literal),
+    ("retfie", 0x0009, Operands::None),
+    ("retlw", 0x3400, Operands::Literal),
+    ("return", 0x0008, Operands::None),
+    ("sleep", 0x0063, Operands::None),
+    ("sublw", 0x3C00, Operands::Literal),
+    ("xorlw", 0x3A00, Operands::Literal),
+];
+
+fn opcode(name: &str) -> Option<(u16, Operands)> {
+    let name = name.to_ascii_lowercase();
+    OPCODES
+        .iter()
+        .find(|(mnemonic, _, _)| *mnemonic == name)
+        .map(|&(_, base, operands)| (base, operands))
+}
+
+fn is_mnemonic(name: &str) -> bool {
+    opcode(name).is_some()
+}
+
+/// One past the highest word address an image holds, the end of data EEPROM.
+const CODE_LIMIT: i64 = EEPROM_ADDRESS as i64 + EEPROM_SIZE as i64;
+/// One past the highest data memory address, across all four banks.
+const RAM_LIMIT: i64 = 0x200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pass {
+    /// Assigns addresses to labels and variables.
+    Symbols,
+    /// Evaluates operands and writes the image.
+    Emit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Code,
+    Data,
+}
+
+struct Assembler {
+    symbols: HashMap<String, i64>,
+    defines: HashMap<String, String>,
+    radix: u32,
+    pass: Pass,
+    section: Section,
+    /// Next program word address.
+    pc: i64,
+    /// Next data RAM address for `res`.
+    ram: i64,
+    /// Next address handed out inside a `cblock`, if one is open.
+    cblock: Option<i64>,
+    image: Image,
+    instructions: Vec<(u16, Instruction)>,
+}
+
+impl Default for Assembler {
+    fn default() -> Self {
+        Self {
+            symbols: HashMap::new(),
+            defines: HashMap::new(),
+            radix: 16,
+            pass: Pass::Symbols,
+            section: Section::Code,
+            pc: 0,
+            ram: 0x20,
+            cblock: None,
+            image: Image::default(),
+            instructions: Vec::new(),
+        }
+    }
+}
+
+impl Assembler {
+    fn pass(&mut self, lines: &[SourceLine], pass: Pass) -> Result<(), AsmError> {
+        self.pass = pass;
+        self.radix = 16;
+        self.section = Section::Code;
+        self.pc = 0;
+        self.ram = 0x20;
+        self.cblock = None;
+        if pass == Pass::Symbols {
+            self.defines.clear();
+        }
+
+        for line in lines {
+            if !self.line(line)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Processes one source line. Returns `false` at the `end` directive.
+    fn line(&mut self, line: &SourceLine) -> Result<bool, AsmError> {
+        let n = line.number;
+        if let Some(next) = self.cblock
+            && !line.text.is_empty()
+            && !line.text.eq_ignore_ascii_case("endc")
+        {
+            // Inside a cblock every line lists names, each optionally
+            // followed by `:size`.
+            let mut address = next;
+            for entry in line.text.split(',') {
+                let (name, size) = match entry.split_once(':') {
+                    Some((name, size)) => (name.trim(), self.eval(size.trim(), n)?),
+                    None => (entry.trim(), 1),
+                };
+                self.define(name, address, n)?;
+                address = address.checked_add(size).ok_or(AsmError::OutOfRange {
+                    line: n,
+                    value: size,
+                })?;
+            }
+            self.cblock = Some(address);
+            return Ok(true);
+        }
+
+        let Some(op) = line.op else {
+            if let Some(label) = line.label {
+                self.define_label(label, n)?;
+            }
+            return Ok(true);
+        };
+        let directive = op.to_ascii_lowercase();
+
+        match directive.as_str() {
+            "equ" | "set" | "constant" => {
+                let value = self.single(line)?;
+                let value = self.eval(value, n)?;
+                let Some(label) = line.label else {
+                    return Err(AsmError::WrongOperandCount {
+                        line: n,
+                        expected: 1,
+                    });
+                };
+                if directive == "equ" {
+                    self.define(label, value, n)?;
+                } else {
+                    self.symbols.insert(label.to_string(), value);
+                }
+                return Ok(true);
+            }
+            "#define" => {
+                let (name, value) = match line.operands.split_first() {
+                    Some((first, rest)) => {
+                        let mut parts = first.splitn(2, char::is_whitespace);
+                        let name = parts.next().unwrap_or_default();
+                        let mut value = parts.next().unwrap_or_default().trim().to_string();
+                        for operand in rest {
+                            value.push(',');
+                            value.push_str(operand);
+                        }
+                        (name.to_string(), value)
+                    }
+                    None => {
+                        return Err(AsmError::WrongOperandCount {
+                            line: n,
+                            expected: 1,
+                        });
+                    }
+                };
+                self.defines.insert(name, value);
+                return Ok(true);
+            }
+            _ => {}
+        }
+
+        if let Some(label) = line.label {
+            // A label on a section directive names the section, not an address.
+            if !matches!(directive.as_str(), "udata" | "udata_shr" | "code") {
+                self.define_label(label, n)?;
+            }
+        }
+
+        match directive.as_str() {
+            "#include" | "include" => {
+                let file = self.single(line)?;
+                let name = file.trim_matches(|c| c == '"' || c == '<' || c == '>');
+                if !name.eq_ignore_ascii_case("p16core.inc") {
+                    return Err(AsmError::UnknownInclude {
+                        line: n,
+                        file: file.to_string(),
+                    });
+                }
+                if self.pass == Pass::Symbols {
+                    for &(name, value) in P16CORE_INC {
+                        self.symbols.insert(name.to_string(), value);
+                    }
+                }
+            }
+            "list" | "nolist" | "processor" | "errorlevel" => {}
+            "radix" => {
+                self.radix = match self.single(line)?.to_ascii_lowercase().as_str() {
+                    "hex" => 16,
+                    "dec" => 10,
+                    "oct" => 8,
+                    other => {
+                        return Err(AsmError::InvalidOperand {
+                            line: n,
+                            operand: other.to_string(),
+                        });
+                    }
+                }
+            }
+            "udata" | "udata_shr" => {
+                self.section = Section::Data;
+                if let Some(address) = line.operands.first() {
+                    self.ram = self.address(address, RAM_LIMIT, n)?;
+                }
+            }
+            "res" => {
+                let size = self.eval(self.single(line)?, n)?;
+                let (next, limit) = match self.section {
+                    Section::Data => (&mut self.ram, RAM_LIMIT),
+                    Section::Code => (&mut self.pc, CODE_LIMIT),
+                };
+                // Reserving up to the very end is fine; going past it is not.
+                *next = next
+                    .checked_add(size)
+                    .filter(|end| size >= 0 && *end <= limit)
+                    .ok_or(AsmError::OutOfRange {
+                        line: n,
+                        value: size,
+                    })?;
+            }
+            "org" | "code" => {
+                self.section = Section::Code;
+                if let Some(address) = line.operands.first() {
+                    self.pc = self.address(address, CODE_LIMIT, n)?;
+                }
+            }
+            "cblock" => {
+                let start = match line.operands.first() {
+                    Some(address) => self.eval(address, n)?,
+                    None => self.ram,
+                };
+                self.cblock = Some(start);
+            }
+            "endc" => {
+                if let Some(next) = self.cblock.take() {
+                    self.ram = next;
+                }
+            }
+            "end" => return Ok(false),
+            "dw" | "data" => {
+                for operand in &line.operands {
+                    let value = self.eval_or_zero(operand, n)?;
+                    self.emit(value, n)?;
+                }
+            }
+            "de" => {
+                for operand in &line.operands {
+                    for byte in self.bytes(operand, n)? {
+                        self.emit(byte, n)?;
+                    }
+                }
+            }
+            "__config" => {
+                let value = self.eval_or_zero(self.single(line)?, n)?;
+                if self.pass == Pass::Emit {
+                    self.image.config = (value & 0x3FFF) as u16;
+                }
+            }
+            "__idlocs" => {
+                let value = self.eval_or_zero(self.single(line)?, n)?;
+                if self.pass == Pass::Emit {
+                    for (i, id) in self.image.id_locations.iter_mut().enumerate() {
+                        *id = ((value >> (12 - 4 * i)) & 0xF) as u16;
+                    }
+                }
+            }
+            "banksel" => {
+                let address = self.eval_or_zero(self.single(line)?, n)?;
+                // bcf/bsf STATUS,RP0 then STATUS,RP1
+                for (bit, bcf) in [(7, 0x1283), (8, 0x1303)] {
+                    let bsf = if (address >> bit) & 1 == 1 { 0x0400 } else { 0 };
+                    self.emit(bcf | bsf, n)?;
+                }
+            }
+            _ => {
+                let Some((base, operands)) = opcode(op) else {
+                    return Err(AsmError::UnknownMnemonic {
+                        line: n,
+                        mnemonic: op.to_string(),
+                    });
+                };
+                let word = match self.pass {
+                    Pass::Symbols => 0,
+                    Pass::Emit => self.encode(base, operands, line)?,
+                };
+                self.emit(word, n)?;
+            }
+        }
+        Ok(true)
+    }
+
+    fn define(&mut self, name: &str, value: i64, line: usize) -> Result<(), AsmError> {
+        if !is_identifier(name) {
+            return Err(AsmError::InvalidOperand {
+                line,
+                operand: name.to_string(),
+            });
+        }
+        if self.pass == Pass::Symbols && self.symbols.insert(name.to_string(), value).is_some() {
+            return Err(AsmError::DuplicateSymbol {
+                line,
+                name: name.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    fn define_label(&mut self, name: &str, line: usize) -> Result<(), AsmError> {
+        let value = match self.section {
+            Section::Code => self.pc,
+            Section::Data => self.ram,
+        };
+        self.define(name, value, line)
+    }
+
+    fn single<'a>(&self, line: &SourceLine<'a>) -> Result<&'a str, AsmError> {
+        match line.operands.as_slice() {
+            [operand] => Ok(operand),
+            _ => Err(AsmError::WrongOperandCount {
+                line: line.number,
+                expected: 1,
+            }),
+        }
+    }
+
+    /// Evaluates an `org` or `udata` address, which must lie below `limit`.
+    fn address(&self, expr: &str, limit: i64, line: usize) -> Result<i64, AsmError> {
+        let value = self.eval(expr, line)?;
+        if !(0..limit).contains(&value) {
+            return Err(AsmError::OutOfRange { line, value });
+        }
+        Ok(value)
+    }
+
+    /// Writes one word at the current address, or only reserves the address
+    /// during the symbol pass.
+    fn emit(&mut self, word: i64, line: usize) -> Result<(), AsmError> {
+        let address = self.pc;
+        self.pc += 1;
+        if self.pass == Pass::Symbols {
+            return Ok(());
+        }
+
+        let out_of_range = AsmError::OutOfRange {
+            line,
+            value: address,
+        };
+        let Ok(address) = u32::try_from(address) else {
+            return Err(out_of_range);
+        };
+        let word = (word & 0x3FFF) as u16;
+        match address {
+            a if (a as usize) < PROGRAM_SIZE => {
+                self.image.program[a as usize] = word;
+                self.instructions.push((a as u16, decode(word)));
+            }
+            a if (ID_ADDRESS..ID_ADDRESS + 4).contains(&a) => {
+                self.image.id_locations[(a - ID_ADDRESS) as usize] = word
+            }
+            CONFIG_ADDRESS => self.image.config = word,
+            a if (EEPROM_ADDRESS..EEPROM_ADDRESS + EEPROM_SIZE as u32).contains(&a) => {
+                self.image.eeprom[(a - EEPROM_ADDRESS) as usize] = word as u8
+            }
+            _ => return Err(out_of_range),
+        }
+        Ok(())
+    }
+
+    fn encode(&self, base: u16, operands: Operands, line: &SourceLine) -> Result<i64, AsmError> {
+        let n = line.number;
+        let expected = match operands {
+            Operands::None => 0,
+            Operands::File | Operands::Literal | Operands::Address => 1,
+            Operands::FileDest | Operands::FileBit => 2,
+        };
+        // A `#define` may stand for several operands, e.g. `#define LED PORTB,3`.
+        let mut args = Vec::new();
+        for operand in &line.operands {
+            match self.defines.get(*operand) {
+                Some(text) => args.extend(split_operands(text, n)?),
+                None => args.push(*operand),
+            }
+        }
+        let count = args.len();
+        // The destination of a file instruction defaults to `f`.
+        let optional_dest = matches!(operands, Operands::FileDest) && count == 1;
+        if count != expected && !optional_dest {
+            return Err(AsmError::WrongOperandCount { line: n, expected });
+        }
+
+        let base = base as i64;
+        let word = match operands {
+            Operands::None => base,
+            Operands::File => base | self.file(args[0], n)?,
+            Operands::FileDest => {
+                let dest = match args.get(1) {
+                    Some(dest) => self.field(dest, 1, n)?,
+                    None => 1,
+                };
+                base | (dest << 7) | self.file(args[0], n)?
+            }
+            Operands::FileBit => {
+                let bit = self.field(args[1], 7, n)?;
+                base | (bit << 7) | self.file(args[0], n)?
+            }
+            Operands::Literal => {
+                let value = self.eval(args[0], n)?;
+                if !(-128..=255).contains(&value) {
+                    return Err(AsmError::OutOfRange { line: n, value });
+                }
+                base | (value & 0xFF)
+            }
+            Operands::Address => {
+                let value = self.eval(args[0], n)?;
+                if !(0..PROGRAM_SIZE as i64).contains(&value) {
+                    return Err(AsmError::OutOfRange { line: n, value });
+                }
+                // The page bits come from PCLATH at run time.
+                base | (value & 0x7FF)
+            }
+        };
+        Ok(word)
+    }
+
+    /// Evaluates a file register operand, dropping the bank bits.
+    fn file(&self, operand: &str, line: usize) -> Result<i64, AsmError> {
+        let value = self.eval(operand, line)?;
+        if !(0..0x200).contains(&value) {
+            return Err(AsmError::OutOfRange { line, value });
+        }
+        Ok(value & 0x7F)
+    }
+
+    fn field(&self, operand: &str, max: i64, line: usize) -> Result<i64, AsmError> {
+        let value = match operand {
+            "w" | "W" => 0,
+            "f" | "F" => 1,
+            _ => self.eval(operand, line)?,
+        };
+        if !(0..=max).contains(&value) {
+            return Err(AsmError::OutOfRange { line, value });
+        }
+        Ok(value)
+    }
+
+    /// Bytes of a `de` operand: a string or a single expression.
+    fn bytes(&self, operand: &str, line: usize) -> Result<Vec<i64>, AsmError> {
+        if let Some(text) = operand
+            .strip_prefix('"')
+            .and_then(|rest| rest.strip_suffix('"'))
+        {
+            return Ok(text.bytes().map(i64::from).collect());
+        }
+        Ok(vec![self.eval_or_zero(operand, line)?])
+    }
+
+    /// Like [`Assembler::eval`], but forward references evaluate to zero
+    /// during the symbol pass where only the word count matters.
+    fn eval_or_zero(&self, expr: &str, line: usize) -> Result<i64, AsmError> {
+        match self.pass {
+            Pass::Symbols => Ok(self.eval(expr, line).unwrap_or(0)),
+            Pass::Emit => self.eval(expr, line),
+        }
+    }
+
+    fn eval(&self, expr: &str, line: usize) -> Result<i64, AsmError> {
+        let tokens = tokenize(expr, self.radix).ok_or_else(|| AsmError::InvalidOperand {
+            line,
+            operand: expr.to_string(),
+        })?;
+        let mut parser = ExprParser {
+            asm: self,
+            tokens: &tokens,
+            pos: 0,
+            line,
+            depth: 0,
+        };
+        let value = parser.expr(0)?;
+        if parser.pos != tokens.len() {
+            return Err(AsmError::InvalidOperand {
+                line,
+                operand: expr.to_string(),
+            });
+        }
+        Ok(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Ident(String),
+    Op(&'static str),
+    Open,
+    Close,
+    Here,
+}
+
+const OPERATORS: &[&str] = &["<<", ">>", "+", "-", "*", "/", "%", "&", "|", "^", "~"];
+
+fn tokenize(expr: &str, radix: u32) -> Option<Vec<Token>> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let rest: String = chars[i..].iter().collect();
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token::Open);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::Close);
+            i += 1;
+        } else if c == '$' {
+            tokens.push(Token::Here);
+            i += 1;
+        } else if c == '\'' {
+            // Character literal
+            if chars.get(i + 2) != Some(&'\'') {
+                return None;
+            }
+            tokens.push(Token::Number(chars[i + 1] as i64));
+            i += 3;
+        } else if chars.get(i + 1) == Some(&'\'') && "hHdDbBoOaA".contains(c) {
+            // Prefixed literal such as H'1F' or D'11'
+            let end = chars[i + 2..].iter().position(|&c| c == '\'')? + i + 2;
+            let digits: String = chars[i + 2..end].iter().collect();
+            let value = match c.to_ascii_lowercase() {
+                'a' => digits.chars().next()? as i64,
+                'h' => i64::from_str_radix(&digits, 16).ok()?,
+                'd' => digits.parse().ok()?,
+                'b' => i64::from_str_radix(&digits, 2).ok()?,
+                _ => i64::from_str_radix(&digits, 8).ok()?,
+            };
+            tokens.push(Token::Number(value));
+            i = end + 1;
+        } else if c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit) {
+            let len = chars[i + 1..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .count();
+            let digits: String = chars[i + 1..i + 1 + len].iter().collect();
+            tokens.push(Token::Number(digits.parse().ok()?));
+            i += 1 + len;
+        } else if c.is_ascii_digit() {
+            let len = chars[i..]
+                .iter()
+                .take_while(|c| c.is_ascii_alphanumeric())
+                .count();
+            let word: String = chars[i..i + len].iter().collect();
+            tokens.push(Token::Number(parse_number(&word, radix)?));
+            i += len;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = chars[i..]
+                .iter()
+                .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+                .count();
+            tokens.push(Token::Ident(chars[i..i + len].iter().collect()));
+            i += len;
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(**op)) {
+            tokens.push(Token::Op(op));
+            i += op.len();
+        } else {
+            return None;
+        }
+    }
+    Some(tokens)
+}
+
+/// Parses a number token such as `0x1F`, `0b101`, `1Fh` or `31` in `radix`.
+fn parse_number(word: &str, radix: u32) -> Option<i64> {
+    let lower = word.to_ascii_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = lower.strip_prefix("0b").filter(|_| radix != 16) {
+        i64::from_str_radix(bin, 2).ok()
+    } else if let Some(hex) = lower.strip_suffix('h') {
+        i64::from_str_radix(hex, 16).ok()
+    } else {
+        i64::from_str_radix(&lower, radix).ok()
+    }
+}
+
+struct ExprParser<'a> {
+    asm: &'a Assembler,
+    tokens: &'a [Token],
+    pos: usize,
+    line: usize,
+    /// Nesting of `#define` expansions, to stop recursive definitions.
+    depth: usize,
+}
+
+impl ExprParser<'_> {
+    fn invalid(&self) -> AsmError {
+        AsmError::InvalidOperand {
+            line: self.line,
+            operand: format!("{:?}", self.tokens),
+        }
+    }
+
+    fn out_of_range(&self, value: i64) -> AsmError {
+        AsmError::OutOfRange {
+            line: self.line,
+            value,
+        }
+    }
+
+    /// Precedence climbing over the binary operators.
+    fn expr(&mut self, min_precedence: u8) -> Result<i64, AsmError> {
+        let mut lhs = self.unary()?;
+        while let Some(Token::Op(op)) = self.tokens.get(self.pos) {
+            let precedence = match *op {
+                "|" => 1,
+                "^" => 2,
+                "&" => 3,
+                "<<" | ">>" => 4,
+                "+" | "-" => 5,
+                "*" | "/" | "%" => 6,
+                _ => return Err(self.invalid()),
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            let rhs = self.expr(precedence + 1)?;
+            lhs = match *op {
+                "|" => lhs | rhs,
+                "^" => lhs ^ rhs,
+                "&" => lhs & rhs,
+                "<<" => lhs << (rhs & 63),
+                ">>" => lhs >> (rhs & 63),
+                "+" => lhs.checked_add(rhs).ok_or_else(|| self.out_of_range(lhs))?,
+                "-" => lhs.checked_sub(rhs).ok_or_else(|| self.out_of_range(lhs))?,
+                "*" => lhs.checked_mul(rhs).ok_or_else(|| self.out_of_range(lhs))?,
+                "/" => lhs.checked_div(rhs).ok_or_else(|| self.invalid())?,
+                _ => lhs.checked_rem(rhs).ok_or_else(|| self.invalid())?,
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<i64, AsmError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| self.invalid())?;
+        self.pos += 1;
+        match token {
+            Token::Number(value) => Ok(value),
+            Token::Here => Ok(self.asm.pc),
+            Token::Op("-") => {
+                let value = self.unary()?;
+                value.checked_neg().ok_or_else(|| self.out_of_range(value))
+            }
+            Token::Op("+") => self.unary(),
+            Token::Op("~") => Ok(!self.unary()?),
+            Token::Open => {
+                let value = self.expr(0)?;
+                if self.tokens.get(self.pos) != Some(&Token::Close) {
+                    return Err(self.invalid());
+                }
+                self.pos += 1;
+                Ok(value)
+            }
+            Token::Ident(name) => match name.to_ascii_lowercase().as_str() {
+                "high" => Ok((self.unary()? >> 8) & 0xFF),
+                "low" => Ok(self.unary()? & 0xFF),
+                _ => self.symbol(&name),
+            },
+            Token::Op(_) | Token::Close => Err(self.invalid()),
+        }
+    }
+
+    fn symbol(&self, name: &str) -> Result<i64, AsmError> {
+        if let Some(&value) = self.asm.symbols.get(name) {
+            return Ok(value);
+        }
+        if let Some(text) = self.asm.defines.get(name)
+            && self.depth < 16
+        {
+            let tokens = tokenize(text, self.asm.radix).ok_or_else(|| self.invalid())?;
+            let mut parser = ExprParser {
+                asm: self.asm,
+                tokens: &tokens,
+                pos: 0,
+                line: self.line,
+                depth: self.depth + 1,
+            };
+            let value = parser.expr(0)?;
+            if parser.pos == tokens.len() {
+                return Ok(value);
+            }
+            return Err(self.invalid());
+        }
+        Err(AsmError::UndefinedSymbol {
+            line: self.line,
+            name: name.to_string(),
+        })
+    }
+}
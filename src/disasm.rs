@@ -0,0 +1,121 @@
+use std::fmt::{self, Write};
+
+use crate::exec::{Instruction, decode};
+
+/// Names of the bank 0 special-function registers, indexed by file address.
+///
+/// Bank 1 only adds OPTION_REG (0x81), TRISA..TRISD (0x85–0x88) and PIE1
+/// (0x8C), which share their 7-bit file address with TMR0, the ports and
+/// PIR1; like MPASM's disassembler we always print the bank 0 name.
+const REGISTER_NAMES: [Option<&str>; 0x20] = [
+    Some("INDF"),
+    Some("TMR0"),
+    Some("PCL"),
+    Some("STATUS"),
+    Some("FSR"),
+    Some("PORTA"),
+    Some("PORTB"),
+    Some("PORTC"),
+    Some("PORTD"),
+    None,
+    Some("PCLATH"),
+    Some("INTCON"),
+    Some("PIR1"),
+    None,
+    Some("INDF1"),
+    Some("INDF2"),
+    Some("T1CON"),
+    Some("T1L"),
+    Some("T1H"),
+    Some("DAN"),
+    Some("DSEG"),
+    None,
+    None,
+    None,
+    Some("RCSTA"),
+    Some("TXREG"),
+    Some("RCREG"),
+    None,
+    Some("PTR1L"),
+    Some("PTR1H"),
+    Some("PTR2L"),
+    Some("PTR2H"),
+];
+
+/// Name of the special-function register at 7-bit file address `reg`.
+pub fn register_name(reg: u8) -> Option<&'static str> {
+    REGISTER_NAMES.get(reg as usize).copied().flatten()
+}
+
+/// Renders `instruction` in MPASM syntax, e.g. `bcf INTCON,7`.
+///
+/// With `symbols` set, special-function register addresses are replaced by
+/// their names; otherwise every file address is printed as hex.
+pub fn format(instruction: Instruction, symbols: bool) -> String {
+    let file = |reg: u8| match register_name(reg) {
+        Some(name) if symbols => name.to_string(),
+        _ => format!("0x{reg:02X}"),
+    };
+    let dest = |dest: bool| if dest { 'f' } else { 'w' };
+
+    match instruction {
+        Instruction::ADDWF { reg, dest: d } => format!("addwf {},{}", file(reg), dest(d)),
+        Instruction::ANDWF { reg, dest: d } => format!("andwf {},{}", file(reg), dest(d)),
+        Instruction::CLRF { reg } => format!("clrf {}", file(reg)),
+        Instruction::CLRW => "clrw".to_string(),
+        Instruction::COMF { reg, dest: d } => format!("comf {},{}", file(reg), dest(d)),
+        Instruction::DECF { reg, dest: d } => format!("decf {},{}", file(reg), dest(d)),
+        Instruction::DECFSZ { reg, dest: d } => format!("decfsz {},{}", file(reg), dest(d)),
+        Instruction::INCF { reg, dest: d } => format!("incf {},{}", file(reg), dest(d)),
+        Instruction::INCFSZ { reg, dest: d } => format!("incfsz {},{}", file(reg), dest(d)),
+        Instruction::IORWF { reg, dest: d } => format!("iorwf {},{}", file(reg), dest(d)),
+        Instruction::MOVF { reg, dest: d } => format!("movf {},{}", file(reg), dest(d)),
+        Instruction::MOVWF { reg } => format!("movwf {}", file(reg)),
+        Instruction::NOP => "nop".to_string(),
+        Instruction::RLF { reg, dest: d } => format!("rlf {},{}", file(reg), dest(d)),
+        Instruction::RRF { reg, dest: d } => format!("rrf {},{}", file(reg), dest(d)),
+        Instruction::SUBWF { reg, dest: d } => format!("subwf {},{}", file(reg), dest(d)),
+        Instruction::SWAPF { reg, dest: d } => format!("swapf {},{}", file(reg), dest(d)),
+        Instruction::XORWF { reg, dest: d } => format!("xorwf {},{}", file(reg), dest(d)),
+        Instruction::BCF { reg, bit } => format!("bcf {},{}", file(reg), bit.as_u8()),
+        Instruction::BSF { reg, bit } => format!("bsf {},{}", file(reg), bit.as_u8()),
+        Instruction::BTFSC { reg, bit } => format!("btfsc {},{}", file(reg), bit.as_u8()),
+        Instruction::BTFSS { reg, bit } => format!("btfss {},{}", file(reg), bit.as_u8()),
+        Instruction::ADDLW { lit } => format!("addlw 0x{lit:02X}"),
+        Instruction::ANDLW { lit } => format!("andlw 0x{lit:02X}"),
+        Instruction::CALL { lit } => format!("call 0x{lit:03X}"),
+        Instruction::CLRWDT => "clrwdt".to_string(),
+        Instruction::GOTO { lit } => format!("goto 0x{lit:03X}"),
+        Instruction::IORLW { lit } => format!("iorlw 0x{lit:02X}"),
+        Instruction::MOVLW { lit } => format!("movlw 0x{lit:02X}"),
+        Instruction::RETFIE => "retfie".to_string(),
+        Instruction::RETLW { lit } => format!("retlw 0x{lit:02X}"),
+        Instruction::RETURN => "return".to_string(),
+        Instruction::SLEEP => "sleep".to_string(),
+        Instruction::SUBLW { lit } => format!("sublw 0x{lit:02X}"),
+        Instruction::XORLW { lit } => format!("xorlw 0x{lit:02X}"),
+        Instruction::Invalid(word) => format!("dw 0x{word:04X}"),
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format(*self, false))
+    }
+}
+
+/// Disassembles `program` into one line per word with its address and raw
+/// value. Trailing unprogrammed (zero) words are left out.
+pub fn listing(program: &[u16], symbols: bool) -> String {
+    let end = program
+        .iter()
+        .rposition(|&word| word != 0)
+        .map_or(0, |last| last + 1);
+
+    let mut out = String::new();
+    for (address, &word) in program[..end].iter().enumerate() {
+        let text = format(decode(word), symbols);
+        writeln!(out, "0x{address:04X}  {word:04X}  {text}").expect("writing to a String");
+    }
+    out
+}
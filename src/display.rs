@@ -0,0 +1,215 @@
+use crate::{
+    p16core::ResetKind,
+    peripheral::{Context, Peripheral},
+};
+
+/// Digits DAN can select, one per bit.
+pub const MAX_DIGITS: usize = 8;
+
+/// Segment bits in DSEG, `a` (bit 0) to `g` (bit 6), then the decimal point.
+pub const SEGMENT_DP: u8 = 1 << 7;
+
+/// Segment patterns of the characters [`decode`] recognises, without the
+/// decimal point.
+const GLYPHS: &[(u8, char)] = &[
+    (0x00, ' '),
+    (0x3F, '0'),
+    (0x06, '1'),
+    (0x5B, '2'),
+    (0x4F, '3'),
+    (0x66, '4'),
+    (0x6D, '5'),
+    (0x7D, '6'),
+    (0x07, '7'),
+    (0x27, '7'),
+    (0x7F, '8'),
+    (0x6F, '9'),
+    (0x67, '9'),
+    (0x77, 'A'),
+    (0x7C, 'b'),
+    (0x39, 'C'),
+    (0x58, 'c'),
+    (0x5E, 'd'),
+    (0x79, 'E'),
+    (0x71, 'F'),
+    (0x76, 'H'),
+    (0x74, 'h'),
+    (0x38, 'L'),
+    (0x5C, 'o'),
+    (0x73, 'P'),
+    (0x50, 'r'),
+    (0x3E, 'U'),
+    (0x1C, 'u'),
+    (0x40, '-'),
+    (0x08, '_'),
+];
+
+/// The character a seven-segment pattern shows, ignoring the decimal point.
+pub fn decode(segments: u8) -> Option<char> {
+    let segments = segments & !SEGMENT_DP;
+    GLYPHS
+        .iter()
+        .find(|&&(pattern, _)| pattern == segments)
+        .map(|&(_, c)| c)
+}
+
+/// A multiplexed seven-segment display behind DAN (0x13, digit anode select)
+/// and DSEG (0x14, segment pattern).
+///
+/// Bit `n` of DAN lights digit `n`, counting from the left, and DSEG holds
+/// the segments of the selected digits; both are active high. Like the eye,
+/// the model integrates what is lit over a persistence window: a segment
+/// counts as visible when it was on for at least `threshold` of the last
+/// window, so multiplexing shows every digit at once while the brief
+/// glitches between digit switches do not.
+#[derive(Debug, Clone)]
+pub struct Display {
+    /// Number of digits wired to DAN, used by [`Display::text`].
+    pub digits: usize,
+    /// Length of the persistence window in milliseconds.
+    pub persistence_ms: u32,
+    /// Fraction of the window a segment must be lit to be seen.
+    pub threshold: f32,
+    dan: u8,
+    dseg: u8,
+    /// Cycles DAN and DSEG have held their current values, not yet credited
+    /// to `lit`.
+    held: u64,
+    /// Cycles into the current window.
+    elapsed: u64,
+    /// Cycles each segment of each digit was lit in the current window.
+    lit: [[u64; 8]; MAX_DIGITS],
+    /// Brightness of each segment over the last complete window.
+    brightness: [[f32; 8]; MAX_DIGITS],
+}
+
+impl Default for Display {
+    fn default() -> Self {
+        Self {
+            digits: 4,
+            persistence_ms: 20,
+            threshold: 0.05,
+            dan: 0,
+            dseg: 0,
+            held: 0,
+            elapsed: 0,
+            lit: [[0; 8]; MAX_DIGITS],
+            brightness: [[0.0; 8]; MAX_DIGITS],
+        }
+    }
+}
+
+impl Display {
+    pub fn dan(&self) -> u8 {
+        self.dan
+    }
+
+    pub fn dseg(&self) -> u8 {
+        self.dseg
+    }
+
+    /// How long each segment of `digit` was lit over the last persistence
+    /// window, from 0.0 to 1.0, segment `a` first and the decimal point last.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `digit` is not below [`MAX_DIGITS`].
+    pub fn brightness(&self, digit: usize) -> [f32; 8] {
+        assert!(digit < MAX_DIGITS, "digit {digit} out of range");
+        self.brightness[digit]
+    }
+
+    /// The segments of `digit` that appear lit, in DSEG bit order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `digit` is not below [`MAX_DIGITS`].
+    pub fn segments(&self, digit: usize) -> u8 {
+        assert!(digit < MAX_DIGITS, "digit {digit} out of range");
+        self.brightness[digit]
+            .iter()
+            .enumerate()
+            .filter(|&(_, &level)| level >= self.threshold)
+            .fold(0, |segments, (bit, _)| segments | 1 << bit)
+    }
+
+    /// What the display shows, one character per digit with a `.` after
+    /// each lit decimal point. Patterns [`decode`] does not know show as
+    /// `?`; blank digits are spaces.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for digit in 0..self.digits.min(MAX_DIGITS) {
+            let segments = self.segments(digit);
+            text.push(decode(segments).unwrap_or('?'));
+            if segments & SEGMENT_DP != 0 {
+                text.push('.');
+            }
+        }
+        text
+    }
+
+    fn window_cycles(&self, clock_hz: u32) -> u64 {
+        (clock_hz as u64 / 4 * self.persistence_ms as u64 / 1000).max(1)
+    }
+
+    /// Adds the time the current DAN/DSEG values have been held to the
+    /// segments they light.
+    fn credit(&mut self) {
+        for digit in (0..MAX_DIGITS).filter(|digit| self.dan & 1 << digit != 0) {
+            for segment in (0..8).filter(|segment| self.dseg & 1 << segment != 0) {
+                self.lit[digit][segment] += self.held;
+            }
+        }
+        self.held = 0;
+    }
+}
+
+impl Peripheral for Display {
+    fn decodes(&self, address: u16) -> bool {
+        matches!(address, 0x013 | 0x014)
+    }
+
+    fn read(&mut self, address: u16, _cx: &mut Context) -> u8 {
+        match address {
+            0x013 => self.dan,
+            _ => self.dseg,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8, _cx: &mut Context) {
+        self.credit();
+        match address {
+            0x013 => self.dan = value,
+            _ => self.dseg = value,
+        }
+    }
+
+    fn tick(&mut self, cx: &mut Context) {
+        self.held += 1;
+        self.elapsed += 1;
+        if self.elapsed < self.window_cycles(cx.clock_hz) {
+            return;
+        }
+        self.credit();
+        for (brightness, lit) in self.brightness.iter_mut().zip(&mut self.lit) {
+            for (level, cycles) in brightness.iter_mut().zip(lit.iter_mut()) {
+                *level = *cycles as f32 / self.elapsed as f32;
+                *cycles = 0;
+            }
+        }
+        self.elapsed = 0;
+    }
+
+    /// Power-on and brown-out resets blank the display. The digit count and
+    /// persistence settings are kept.
+    fn reset(&mut self, kind: ResetKind) {
+        if matches!(kind, ResetKind::PowerOn | ResetKind::BrownOut) {
+            *self = Self {
+                digits: self.digits,
+                persistence_ms: self.persistence_ms,
+                threshold: self.threshold,
+                ..Self::default()
+            };
+        }
+    }
+}
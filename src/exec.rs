@@ -1,7 +1,6 @@
-use crate::P16Core;
 use std::ops::Shl;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Bit {
     B0 = 0,
     B1 = 1,
@@ -92,7 +91,9 @@ type B = Bit;
 type K = u8;
 type A = u16;
 
-#[derive(Debug, Clone, Copy)]
+/// A decoded instruction. Reserved encodings, e.g. data table words executed
+/// as code, decode to `Invalid` carrying the raw word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Instruction {
     ADDWF { reg: F, dest: D },
     ANDWF { reg: F, dest: D },
@@ -119,14 +120,18 @@ pub enum Instruction {
     ADDLW { lit: K },
     ANDLW { lit: K },
     CALL { lit: A },
+    CLRWDT,
     GOTO { lit: A },
     IORLW { lit: K },
     MOVLW { lit: K },
     RETFIE,
     RETLW { lit: K },
     RETURN,
+    SLEEP,
     SUBLW { lit: K },
     XORLW { lit: K },
+    /// A reserved opcode, or a word wider than 14 bits.
+    Invalid(u16),
 }
 
 #[cfg_attr(feature = "trace", tracing::instrument)]
@@ -136,13 +141,9 @@ pub fn decode(word: u16) -> Instruction {
             0b00_0000 => match (word & 0xff) as u8 {
                 0b0000_0000 | 0b0010_0000 | 0b0100_0000 | 0b0110_0000 => Instruction::NOP,
                 0b0000_1000 => Instruction::RETURN,
-                0b0110_0100 => {
-                    panic!("CLRWDT OPCODE {word}")
-                }
+                0b0110_0100 => Instruction::CLRWDT,
                 0b0000_1001 => Instruction::RETFIE,
-                0b0110_0011 => {
-                    panic!("SLEEP OPCODE {word}")
-                }
+                0b0110_0011 => Instruction::SLEEP,
                 0b1000_0000..=0b1111_1111 => Instruction::MOVWF {
                     reg: (word & 0b0111_1111) as u8,
                 },
@@ -151,9 +152,7 @@ pub fn decode(word: u16) -> Instruction {
                 | 0b0010_0001..=0b0011_1111
                 | 0b0100_0000..=0b0101_1111
                 | 0b0110_0001..=0b0110_0010
-                | 0b0110_0101..=0b0111_1111 => {
-                    panic!("UNKNOWN OPCODE {word}")
-                }
+                | 0b0110_0101..=0b0111_1111 => Instruction::Invalid(word),
             },
             0b00_0001 => match (word >> 7) & 1 == 0 {
                 true => Instruction::CLRW,
@@ -217,8 +216,7 @@ pub fn decode(word: u16) -> Instruction {
                 reg: (word & 0b0111_1111) as u8,
                 dest: (word >> 7) & 1 == 1,
             },
-            0b01_0000..=0b11_1111 => panic!("Unexpected OPCODE in 0b00_... section"),
-            0b0100_0000..=0b1111_1111 => unreachable!(),
+            0b01_0000..=0b1111_1111 => unreachable!(),
         },
         0b01_0000..=0b01_1111 => {
             let reg = (word & 0b0111_1111) as u8;
@@ -260,252 +258,61 @@ pub fn decode(word: u16) -> Instruction {
             0b1010 => Instruction::XORLW {
                 lit: (word & 0xff) as u8,
             },
-            0b1011 => panic!("UNKNOWN OPCODE {word}"),
+            0b1011 => Instruction::Invalid(word),
             0b0001_0000..=0b1111_1111 => unreachable!(),
         },
-        0b0100_0000..=0b1111_1111 => unreachable!(),
+        // Wider than an opcode word, e.g. written through `program_mut`.
+        0b0100_0000..=0b1111_1111 => Instruction::Invalid(word),
     }
 }
 
-#[cfg_attr(feature = "trace", tracing::instrument(skip(core)))]
-pub fn exec_op(core: &mut P16Core, instruction: Instruction) {
+/// Encodes `instruction` as a 14-bit opcode word, the inverse of [`decode`].
+///
+/// Operands wider than their field are truncated: a register above 0x7F
+/// keeps its low seven bits, a CALL or GOTO target its low eleven and an
+/// `Invalid` word its low fourteen. Don't-care bits are encoded as zero. So
+/// `decode(encode(i)) == i` only holds for instructions as [`decode`]
+/// produces them, and `encode(decode(word))` only equals `word` for
+/// canonical encodings.
+pub fn encode(instruction: Instruction) -> u16 {
+    let file = |base: u16, reg: F, dest: D| base | (dest as u16) << 7 | (reg as u16 & 0x7F);
+    let bit = |base: u16, reg: F, bit: B| base | bit.as_u16() << 7 | (reg as u16 & 0x7F);
     match instruction {
-        Instruction::ADDWF { reg, dest } => {
-            let b = core.read(reg as u16);
-            let (result, c) = core.w.overflowing_add(b);
-
-            core.status.z = result == 0;
-            core.status.c = c;
-            core.status.dc = ((core.w & 0x0F) + (b & 0x0F)) > 0x0F;
-
-            if dest {
-                core.write(reg as u16, result);
-            } else {
-                core.w = result;
-            }
-        }
-        Instruction::ANDWF { reg, dest } => {
-            let result = core.w & core.read(reg as u16);
-
-            if dest {
-                core.write(reg as u16, result);
-            } else {
-                core.w = result;
-            }
-
-            core.status.z = core.w == 0;
-        }
-        Instruction::CLRF { reg } => {
-            core.write(reg as u16, 0);
-            core.status.z = true;
-        }
-        Instruction::CLRW => {
-            core.w = 0;
-            core.status.z = true;
-        }
-        Instruction::COMF { reg, dest } => {
-            let result = !core.read(reg as u16);
-            core.status.z = result == 0;
-            if dest {
-                core.write(reg as u16, result);
-            } else {
-                core.w = result;
-            }
-        }
-        Instruction::DECF { reg, dest } => {
-            let result = core.read(reg as u16) - 1;
-            core.status.z = result == 0;
-            if dest {
-                core.write(reg as u16, result);
-            } else {
-                core.w = result;
-            }
-        }
-        Instruction::DECFSZ { reg, dest } => {
-            let result = core.read(reg as u16) - 1;
-            if result == 0 {
-                core.skip_next = true;
-            }
-            if dest {
-                core.write(reg as u16, result);
-            } else {
-                core.w = result;
-            }
-        }
-        Instruction::INCF { reg, dest } => {
-            let result = core.read(reg as u16) + 1;
-            core.status.z = result == 0;
-            if dest {
-                core.write(reg as u16, result);
-            } else {
-                core.w = result;
-            }
-        }
-        Instruction::INCFSZ { reg, dest } => {
-            let result = core.read(reg as u16) + 1;
-            if result == 0 {
-                core.skip_next = true;
-            }
-            if dest {
-                core.write(reg as u16, result);
-            } else {
-                core.w = result;
-            }
-        }
-        Instruction::IORWF { reg, dest } => {
-            let result = core.w | core.read(reg as u16);
-            core.status.z = result == 0;
-            if dest {
-                core.write(reg as u16, result);
-            } else {
-                core.w = result;
-            }
-        }
-        Instruction::MOVF { reg, dest } => {
-            let f = core.read(reg as u16);
-
-            if dest {
-                core.write(reg as u16, f);
-            } else {
-                core.w = f;
-            }
-        }
-        Instruction::MOVWF { reg } => {
-            core.write(reg as u16, core.w);
-        }
-        Instruction::NOP => {}
-        Instruction::RLF { reg, dest } => {
-            let v = core.read(reg as u16);
-
-            let c = v >> 7;
-            let result = (v << 1) | core.status.c as u8;
-            core.status.c = c == 1;
-
-            if dest {
-                core.write(reg as u16, result);
-            } else {
-                core.w = v;
-            }
-        }
-        Instruction::RRF { reg, dest } => {
-            let v = core.read(reg as u16);
-
-            let c = v & 1;
-            let result = (v >> 1) | ((core.status.c as u8) << 7);
-            core.status.c = c == 1;
-
-            if dest {
-                core.write(reg as u16, result);
-            } else {
-                core.w = result;
-            }
-        }
-        Instruction::SUBWF { reg, dest } => {
-            let f = core.read(reg as u16);
-            let (result, c) = f.overflowing_sub(core.w);
-
-            core.status.z = result == 0;
-            core.status.c = c;
-            core.status.dc = (f & 0x0F) >= (core.w & 0x0F);
-
-            if dest {
-                core.write(reg as u16, result);
-            } else {
-                core.w = result;
-            }
-        }
-        Instruction::SWAPF { reg, dest } => {
-            let f = core.read(reg as u16);
-            let result = ((f & 0xF0) >> 4) | ((f & 0xF) << 4);
-
-            if dest {
-                core.write(reg as u16, result);
-            } else {
-                core.w = result;
-            }
-        }
-        Instruction::XORWF { reg, dest } => {
-            let f = core.read(reg as u16);
-            let result = core.w ^ f;
-
-            core.status.z = result == 0;
-
-            if dest {
-                core.write(reg as u16, result);
-            } else {
-                core.w = result;
-            }
-        }
-        Instruction::BCF { reg, bit } => {
-            let result = core.read(reg as u16) & !(1u8 << bit);
-            core.write(reg as u16, result);
-        }
-        Instruction::BSF { reg, bit } => {
-            let result = core.read(reg as u16) | (1u8 << bit);
-            core.write(reg as u16, result);
-        }
-        Instruction::BTFSC { reg, bit } => {
-            let bit = core.read(reg as u16) & (1u8 << bit) == 1;
-            if !bit {
-                core.skip_next = true;
-            }
-        }
-        Instruction::BTFSS { reg, bit } => {
-            let bit = core.read(reg as u16) & (1u8 << bit) == 1;
-            if bit {
-                core.skip_next = true;
-            }
-        }
-        Instruction::ADDLW { lit } => {
-            let (result, c) = core.w.overflowing_add(lit);
-
-            core.status.z = result == 0;
-            core.status.c = c;
-            core.status.dc = ((core.w & 0x0F) + (lit & 0x0F)) > 0x0F;
-
-            core.w = result;
-        }
-        Instruction::ANDLW { lit } => {
-            core.w &= lit;
-            core.status.z = core.w == 0;
-        }
-        Instruction::CALL { lit } => {
-            core.stack.push_front(core.pc);
-            core.pc = (core.pclath as u16 & 0x18 << 7) | lit & 0x3FF;
-        }
-        Instruction::GOTO { lit } => {
-            core.pc = (core.pclath as u16 & 0x18 << 7) | lit & 0x3FF;
-        }
-        Instruction::IORLW { lit } => {
-            core.w |= lit;
-            core.status.z = core.w == 0;
-        }
-        Instruction::MOVLW { lit } => {
-            core.w = lit;
-        }
-        Instruction::RETFIE => {
-            core.pc = core.stack.pop_front().unwrap();
-            core.intcon.gie = true;
-        }
-        Instruction::RETLW { lit } => {
-            core.w = lit;
-            core.pc = core.stack.pop_front().unwrap();
-        }
-        Instruction::RETURN => {
-            core.pc = core.stack.pop_front().unwrap();
-        }
-        Instruction::SUBLW { lit } => {
-            let (result, c) = lit.overflowing_sub(core.w);
-
-            core.status.z = result == 0;
-            core.status.c = c;
-            core.status.dc = (lit & 0x0F) >= (core.w & 0x0F);
-
-            core.w = result;
-        }
-        Instruction::XORLW { lit } => {
-            core.w ^= lit;
-            core.status.z = core.w == 0;
-        }
+        Instruction::ADDWF { reg, dest } => file(0x0700, reg, dest),
+        Instruction::ANDWF { reg, dest } => file(0x0500, reg, dest),
+        Instruction::CLRF { reg } => file(0x0100, reg, true),
+        Instruction::CLRW => 0x0100,
+        Instruction::COMF { reg, dest } => file(0x0900, reg, dest),
+        Instruction::DECF { reg, dest } => file(0x0300, reg, dest),
+        Instruction::DECFSZ { reg, dest } => file(0x0B00, reg, dest),
+        Instruction::INCF { reg, dest } => file(0x0A00, reg, dest),
+        Instruction::INCFSZ { reg, dest } => file(0x0F00, reg, dest),
+        Instruction::IORWF { reg, dest } => file(0x0400, reg, dest),
+        Instruction::MOVF { reg, dest } => file(0x0800, reg, dest),
+        Instruction::MOVWF { reg } => file(0x0000, reg, true),
+        Instruction::NOP => 0x0000,
+        Instruction::RLF { reg, dest } => file(0x0D00, reg, dest),
+        Instruction::RRF { reg, dest } => file(0x0C00, reg, dest),
+        Instruction::SUBWF { reg, dest } => file(0x0200, reg, dest),
+        Instruction::SWAPF { reg, dest } => file(0x0E00, reg, dest),
+        Instruction::XORWF { reg, dest } => file(0x0600, reg, dest),
+        Instruction::BCF { reg, bit: b } => bit(0x1000, reg, b),
+        Instruction::BSF { reg, bit: b } => bit(0x1400, reg, b),
+        Instruction::BTFSC { reg, bit: b } => bit(0x1800, reg, b),
+        Instruction::BTFSS { reg, bit: b } => bit(0x1C00, reg, b),
+        Instruction::ADDLW { lit } => 0x3E00 | lit as u16,
+        Instruction::ANDLW { lit } => 0x3900 | lit as u16,
+        Instruction::CALL { lit } => 0x2000 | (lit & 0x7FF),
+        Instruction::CLRWDT => 0x0064,
+        Instruction::GOTO { lit } => 0x2800 | (lit & 0x7FF),
+        Instruction::IORLW { lit } => 0x3800 | lit as u16,
+        Instruction::MOVLW { lit } => 0x3000 | lit as u16,
+        Instruction::RETFIE => 0x0009,
+        Instruction::RETLW { lit } => 0x3400 | lit as u16,
+        Instruction::RETURN => 0x0008,
+        Instruction::SLEEP => 0x0063,
+        Instruction::SUBLW { lit } => 0x3C00 | lit as u16,
+        Instruction::XORLW { lit } => 0x3A00 | lit as u16,
+        Instruction::Invalid(word) => word & 0x3FFF,
     }
 }
@@ -0,0 +1,356 @@
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+/// Number of 14-bit words in program memory.
+pub const PROGRAM_SIZE: usize = 4096;
+/// Number of bytes of data EEPROM.
+pub const EEPROM_SIZE: usize = 256;
+
+/// Word address of the first user ID location.
+pub const ID_ADDRESS: u32 = 0x2000;
+/// Word address of the configuration word.
+pub const CONFIG_ADDRESS: u32 = 0x2007;
+/// Word address at which data EEPROM contents are stored in HEX files.
+pub const EEPROM_ADDRESS: u32 = 0x2100;
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    /// A record contains characters that are not hex digits.
+    InvalidHex {
+        line: usize,
+    },
+    /// A record is too short to hold a header and checksum.
+    ShortRecord {
+        line: usize,
+    },
+    /// A record's byte count disagrees with the data it holds.
+    LengthMismatch {
+        line: usize,
+        count: usize,
+        found: usize,
+    },
+    Checksum {
+        line: usize,
+        expected: u8,
+        found: u8,
+    },
+    /// A data record targets a word address outside program memory, the
+    /// configuration area and data EEPROM.
+    AddressOutOfRange {
+        line: usize,
+        address: u32,
+    },
+    UnsupportedRecord {
+        line: usize,
+        rtype: u8,
+    },
+    /// The file ends without an end-of-file record, e.g. because it was
+    /// truncated.
+    MissingEof,
+    /// A raw binary image does not hold a whole number of 16-bit words.
+    OddLength {
+        len: usize,
+    },
+    /// The program holds more words than fit in program memory.
+    TooLarge {
+        words: usize,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "failed to read program: {err}"),
+            LoadError::InvalidHex { line } => write!(f, "line {line}: invalid hex digits"),
+            LoadError::ShortRecord { line } => write!(f, "line {line}: record is too short"),
+            LoadError::LengthMismatch { line, count, found } => write!(
+                f,
+                "line {line}: byte count is {count} but the record holds {found} data bytes"
+            ),
+            LoadError::Checksum {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line}: checksum mismatch (expected 0x{expected:02X}, found 0x{found:02X})"
+            ),
+            LoadError::AddressOutOfRange { line, address } => write!(
+                f,
+                "line {line}: word address 0x{address:04X} is outside program memory"
+            ),
+            LoadError::UnsupportedRecord { line, rtype } => {
+                write!(f, "line {line}: unsupported record type 0x{rtype:02X}")
+            }
+            LoadError::MissingEof => write!(f, "missing end-of-file record"),
+            LoadError::OddLength { len } => {
+                write!(f, "binary image length {len} is not a multiple of 2")
+            }
+            LoadError::TooLarge { words } => write!(
+                f,
+                "program of {words} words does not fit in {PROGRAM_SIZE} words of program memory"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(err: std::io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+/// A program memory image ready to be loaded into a core.
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub program: [u16; PROGRAM_SIZE],
+    pub id_locations: [u16; 4],
+    pub config: u16,
+    pub eeprom: [u8; EEPROM_SIZE],
+}
+
+impl Default for Image {
+    fn default() -> Self {
+        Self {
+            program: [0; PROGRAM_SIZE],
+            id_locations: [0x3FFF; 4],
+            config: 0x3FFF,
+            eeprom: [0xFF; EEPROM_SIZE],
+        }
+    }
+}
+
+impl Image {
+    pub fn from_hex_str(contents: &str) -> Result<Self, LoadError> {
+        parse_hex(contents)
+    }
+
+    /// Parses Intel HEX from raw bytes, e.g. a file embedded with `include_bytes!`.
+    pub fn from_hex_bytes(bytes: &[u8]) -> Result<Self, LoadError> {
+        parse_hex(&String::from_utf8_lossy(bytes))
+    }
+
+    pub fn from_hex_reader(mut reader: impl Read) -> Result<Self, LoadError> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        parse_hex(&contents)
+    }
+
+    pub fn from_hex_file(path: impl AsRef<std::path::Path>) -> Result<Self, LoadError> {
+        parse_hex(&std::fs::read_to_string(path)?)
+    }
+
+    /// Loads Intel HEX or a raw binary, telling them apart by content: HEX
+    /// starts with a `:` record mark, possibly after whitespace, so a binary
+    /// whose first byte is 0x3A is read as HEX.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LoadError> {
+        if bytes.trim_ascii_start().starts_with(b":") {
+            Self::from_hex_bytes(bytes)
+        } else {
+            Self::from_binary(bytes)
+        }
+    }
+
+    /// Loads an Intel HEX or raw binary file, see [`Image::from_bytes`].
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, LoadError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// Loads a raw binary of little-endian 16-bit words starting at address 0.
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, LoadError> {
+        if !bytes.len().is_multiple_of(2) {
+            return Err(LoadError::OddLength { len: bytes.len() });
+        }
+        let words: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        Self::from_words(&words)
+    }
+
+    /// Loads program words starting at address 0. Bits above 14 are dropped.
+    pub fn from_words(words: &[u16]) -> Result<Self, LoadError> {
+        if words.len() > PROGRAM_SIZE {
+            return Err(LoadError::TooLarge { words: words.len() });
+        }
+        let mut image = Self::default();
+        for (slot, word) in image.program.iter_mut().zip(words) {
+            *slot = word & 0x3FFF;
+        }
+        Ok(image)
+    }
+
+    /// Writes the image as Intel HEX (INHX32), the format MPASM produces.
+    ///
+    /// Program rows that are entirely zero, erased EEPROM rows and erased ID
+    /// locations and configuration word are left out, so loading the output
+    /// yields the same image.
+    pub fn write_hex(&self, mut out: impl Write) -> io::Result<()> {
+        let mut upper_addr = None;
+
+        for (row, words) in self.program.chunks(8).enumerate() {
+            if words.iter().any(|&word| word != 0) {
+                write_data(&mut out, &mut upper_addr, (row * 8) as u32, words)?;
+            }
+        }
+        if self.id_locations.iter().any(|&word| word != 0x3FFF) {
+            write_data(&mut out, &mut upper_addr, ID_ADDRESS, &self.id_locations)?;
+        }
+        if self.config != 0x3FFF {
+            write_data(&mut out, &mut upper_addr, CONFIG_ADDRESS, &[self.config])?;
+        }
+        for (row, bytes) in self.eeprom.chunks(8).enumerate() {
+            if bytes.iter().any(|&byte| byte != 0xFF) {
+                let words: Vec<u16> = bytes.iter().map(|&byte| byte as u16).collect();
+                let address = EEPROM_ADDRESS + (row * 8) as u32;
+                write_data(&mut out, &mut upper_addr, address, &words)?;
+            }
+        }
+
+        write_record(&mut out, 0x01, 0x0000, &[])
+    }
+
+    pub fn to_hex(&self) -> String {
+        let mut out = Vec::new();
+        self.write_hex(&mut out)
+            .expect("writing to a Vec cannot fail");
+        String::from_utf8(out).expect("HEX output is ASCII")
+    }
+
+    /// Stores a word read from a HEX file at word `address`, routing it to
+    /// program memory, the ID locations, the configuration word or data EEPROM.
+    fn store(&mut self, address: u32, word: u16) -> Option<()> {
+        let word = word & 0x3FFF;
+        match address {
+            a if (a as usize) < PROGRAM_SIZE => self.program[a as usize] = word,
+            a if (ID_ADDRESS..ID_ADDRESS + 4).contains(&a) => {
+                self.id_locations[(a - ID_ADDRESS) as usize] = word
+            }
+            CONFIG_ADDRESS => self.config = word,
+            a if (EEPROM_ADDRESS..EEPROM_ADDRESS + EEPROM_SIZE as u32).contains(&a) => {
+                self.eeprom[(a - EEPROM_ADDRESS) as usize] = word as u8
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+fn parse_hex(contents: &str) -> Result<Image, LoadError> {
+    let mut image = Image::default();
+    let mut upper_addr = 0u32;
+
+    for (index, line) in contents.lines().enumerate() {
+        let line_no = index + 1;
+        let line = line.trim();
+        let Some(record) = line.strip_prefix(':') else {
+            continue;
+        };
+        let bytes = hex::decode(record).map_err(|_| LoadError::InvalidHex { line: line_no })?;
+
+        if bytes.len() < 5 {
+            return Err(LoadError::ShortRecord { line: line_no });
+        }
+        if bytes.len() != 5 + bytes[0] as usize {
+            return Err(LoadError::LengthMismatch {
+                line: line_no,
+                count: bytes[0] as usize,
+                found: bytes.len() - 5,
+            });
+        }
+
+        let count = bytes[0] as usize;
+        let addr = ((bytes[1] as u16) << 8 | bytes[2] as u16) as u32;
+        let rtype = bytes[3];
+        let data = &bytes[4..4 + count];
+
+        let found = bytes[4 + count];
+        let sum = bytes[..4 + count]
+            .iter()
+            .fold(0u8, |acc, b| acc.wrapping_add(*b));
+        let expected = sum.wrapping_neg();
+        if found != expected {
+            return Err(LoadError::Checksum {
+                line: line_no,
+                expected,
+                found,
+            });
+        }
+
+        match rtype {
+            0x00 => {
+                let full_addr = (upper_addr << 16) + addr;
+                for i in (0..count).step_by(2) {
+                    let lo = data[i];
+                    let hi = if i + 1 < count { data[i + 1] } else { 0 };
+                    let word = ((hi as u16) << 8) | lo as u16;
+                    let address = (full_addr + i as u32) / 2;
+                    image
+                        .store(address, word)
+                        .ok_or(LoadError::AddressOutOfRange {
+                            line: line_no,
+                            address,
+                        })?;
+                }
+            }
+            0x04 => {
+                if count != 2 {
+                    return Err(LoadError::ShortRecord { line: line_no });
+                }
+                upper_addr = ((data[0] as u32) << 8) | data[1] as u32;
+            }
+            0x01 => return Ok(image),
+            _ => {
+                return Err(LoadError::UnsupportedRecord {
+                    line: line_no,
+                    rtype,
+                });
+            }
+        }
+    }
+
+    Err(LoadError::MissingEof)
+}
+
+/// Writes `words` starting at word `address` as type 00 records, preceded by a
+/// type 04 record whenever the upper 16 bits of the byte address change.
+fn write_data(
+    out: &mut impl Write,
+    upper_addr: &mut Option<u16>,
+    address: u32,
+    words: &[u16],
+) -> io::Result<()> {
+    let byte_addr = address * 2;
+    let upper = (byte_addr >> 16) as u16;
+    if *upper_addr != Some(upper) {
+        write_record(out, 0x04, 0x0000, &upper.to_be_bytes())?;
+        *upper_addr = Some(upper);
+    }
+
+    let data: Vec<u8> = words.iter().flat_map(|word| word.to_le_bytes()).collect();
+    write_record(out, 0x00, byte_addr as u16, &data)
+}
+
+fn write_record(out: &mut impl Write, rtype: u8, addr: u16, data: &[u8]) -> io::Result<()> {
+    let mut bytes = Vec::with_capacity(data.len() + 5);
+    bytes.push(data.len() as u8);
+    bytes.extend_from_slice(&addr.to_be_bytes());
+    bytes.push(rtype);
+    bytes.extend_from_slice(data);
+    let sum = bytes.iter().fold(0u8, |acc, b| acc.wrapping_add(*b));
+    bytes.push(sum.wrapping_neg());
+
+    writeln!(out, ":{}", hex::encode_upper(bytes))
+}
@@ -0,0 +1,34 @@
+//! Simulator for the p16core, a PIC16-style 14-bit microcontroller core.
+//!
+//! The [`P16Core`] type holds the whole machine state. Load a program with
+//! [`P16Core::new`] or build an [`Image`] from memory and pass it to
+//! [`P16Core::from_image`], advance it with [`P16Core::step`] and inspect or modify
+//! registers through [`P16Core::read`] and [`P16Core::write`].
+//!
+//! Timers, ports, the UART and the display are [`peripheral::Peripheral`]s on
+//! the core's bus; custom devices can be attached through [`P16Core::bus_mut`].
+
+pub mod alu;
+pub mod asm;
+pub mod disasm;
+pub mod display;
+pub mod exec;
+pub mod image;
+pub mod mem;
+pub mod p16core;
+pub mod peripheral;
+pub mod ports;
+pub mod regs;
+pub mod serial;
+pub mod stack;
+pub mod tmr0;
+pub mod tmr1;
+pub mod uart;
+pub mod wdt;
+
+pub use exec::{Bit, Instruction};
+pub use image::{Image, LoadError};
+pub use p16core::{
+    DEFAULT_CLOCK_HZ, Halt, InvalidOpcodePolicy, P16Core, ResetKind, UnimplementedAccessPolicy,
+};
+pub use ports::Port;
@@ -1,9 +1,4 @@
-pub mod exec;
-pub mod mem;
-pub mod p16core;
-pub mod regs;
-
-// use std::thread;
+use std::thread;
 use std::time::{Duration, Instant};
 
 #[cfg(feature = "trace")]
@@ -11,7 +6,11 @@ use tracing::Level;
 #[cfg(feature = "trace")]
 use tracing_subscriber::FmtSubscriber;
 
-use crate::p16core::P16Core;
+use p16core_sim::{
+    P16Core, UnimplementedAccessPolicy, disasm,
+    serial::{Backend, Connection},
+    uart::Uart,
+};
 
 #[cfg(feature = "pprof")]
 use pprof::ProfilerGuard;
@@ -19,6 +18,11 @@ use pprof::ProfilerGuard;
 #[cfg(feature = "flame")]
 use flame;
 
+const USAGE: &str = "usage: p16core-sim [FILE.hex|FILE.bin] [--cycles N] [--stack-diagnostics]
+                   [--unimplemented zero|warn|stop]
+                   [--uart stdio|pty|tcp:[HOST:]PORT]
+       p16core-sim disasm FILE.hex [--numeric]";
+
 fn main() {
     #[cfg(feature = "pprof")]
     let guard = ProfilerGuard::new(10000).unwrap();
@@ -29,50 +33,129 @@ fn main() {
     #[cfg(feature = "trace")]
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
 
-    const CPU_FREQ_HZ: u64 = 20_000_000;
-    let cycle_duration = Duration::from_nanos(1_000_000_000 / CPU_FREQ_HZ);
+    const CPU_FREQ_HZ: u32 = 20_000_000;
+    // How far the simulation may run ahead of the wall clock before sleeping.
+    const PACING_SLACK: Duration = Duration::from_millis(1);
+    // Each instruction cycle takes four oscillator clocks.
+    let cycle_ns = 4_000_000_000 / CPU_FREQ_HZ as u64;
+
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().map(String::as_str) == Some("disasm") {
+        args.next();
+        disassemble(args);
+        return;
+    }
+
+    let mut file = String::from("test/src.X.production.hex");
+    let mut cycles = None;
+    let mut stack_diagnostics = false;
+    let mut unimplemented = UnimplementedAccessPolicy::Warn;
+    let mut uart = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--cycles" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => cycles = Some(n),
+                None => {
+                    eprintln!("{USAGE}");
+                    std::process::exit(2);
+                }
+            },
+            "--stack-diagnostics" => stack_diagnostics = true,
+            "--unimplemented" => {
+                unimplemented = match args.next().as_deref() {
+                    Some("zero") => UnimplementedAccessPolicy::ReadZero,
+                    Some("warn") => UnimplementedAccessPolicy::Warn,
+                    Some("stop") => UnimplementedAccessPolicy::Stop,
+                    _ => {
+                        eprintln!("{USAGE}");
+                        std::process::exit(2);
+                    }
+                }
+            }
+            "--uart" => match args.next().map(|backend| backend.parse::<Backend>()) {
+                Some(Ok(backend)) => uart = Some(backend),
+                Some(Err(err)) => {
+                    eprintln!("{err}");
+                    std::process::exit(2);
+                }
+                None => {
+                    eprintln!("{USAGE}");
+                    std::process::exit(2);
+                }
+            },
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            _ => file = arg,
+        }
+    }
 
-    let mut next_tick = Instant::now();
-    let file = "test/src.X.production.hex";
-    let mut p16 = P16Core::new(file);
+    let mut p16 = load(&file);
+    p16.set_clock_hz(CPU_FREQ_HZ);
+    p16.stack.set_diagnostics(stack_diagnostics);
+    p16.unimplemented_access_policy = unimplemented;
+
+    // With the UART connected, run in real time until stopped unless a
+    // cycle count was given.
+    let connection = uart.map(|backend| {
+        let host = p16.bus().get::<Uart>().unwrap().host();
+        match Connection::open(&backend, host) {
+            Ok(connection) => {
+                eprintln!("UART connected to {}", connection.description());
+                connection
+            }
+            Err(err) => {
+                eprintln!("cannot open UART backend: {err}");
+                std::process::exit(1);
+            }
+        }
+    });
+    let realtime = connection.is_some();
+    let cycles = cycles.unwrap_or(if realtime {
+        u64::MAX
+    } else {
+        CPU_FREQ_HZ as u64 / 4
+    });
 
     let run_start = Instant::now();
 
-    for _ in 0..CPU_FREQ_HZ {
+    while p16.cycles() < cycles {
         #[cfg(feature = "flame")]
         flame::start("cycle");
 
-        #[cfg(feature = "flame")]
-        flame::start("get_next_op");
-        let op = p16.get_next_op();
-        #[cfg(feature = "flame")]
-        flame::end("get_next_op");
-
-        #[cfg(feature = "flame")]
-        flame::start("decode");
-        let instruction = P16Core::decode(op);
-        #[cfg(feature = "flame")]
-        flame::end("decode");
-
-        #[cfg(feature = "flame")]
-        flame::start("exec_op");
-        p16.exec_op(instruction);
-        #[cfg(feature = "flame")]
-        flame::end("exec_op");
+        p16.step();
 
         #[cfg(feature = "flame")]
         flame::end("cycle");
 
-        next_tick += cycle_duration;
+        for event in p16.stack.take_events() {
+            eprintln!("{event}");
+        }
+        for warning in p16.take_warnings() {
+            eprintln!("warning: {warning}");
+        }
+        if let Some(halt) = p16.halted() {
+            eprintln!("simulation halted: {halt}");
+            break;
+        }
+        if p16.sleeping() && p16.sleep_until_wake().is_none() {
+            eprintln!("core is asleep with no wake-up source");
+            break;
+        }
+
+        let next_tick = run_start + Duration::from_nanos(cycle_ns * p16.cycles());
         let now = Instant::now();
-        if now < next_tick {
-            // thread::sleep(next_tick - now);
-        } else {
-            next_tick = now;
+        if realtime && next_tick > now + PACING_SLACK {
+            thread::sleep(next_tick - now);
         }
     }
 
-    println!("{:?}", run_start.elapsed());
+    if let Some(connection) = &connection {
+        connection.flush();
+    }
+
+    eprintln!("{:?}", run_start.elapsed());
 
     // --- Dump flamegraph if feature enabled ---
     #[cfg(feature = "flame")]
@@ -80,7 +163,7 @@ fn main() {
         use std::fs::File;
         let file = File::create("flamegraph.html").unwrap();
         flame::dump_html(file).unwrap();
-        println!("Flamegraph written to flamegraph.html");
+        eprintln!("Flamegraph written to flamegraph.html");
     }
 
     // --- Dump pprof SVG if feature enabled ---
@@ -90,9 +173,37 @@ fn main() {
         if let Ok(report) = guard.report().build() {
             let file = File::create("flamegraph.svg").unwrap();
             report.flamegraph(file).unwrap();
-            println!("pprof flamegraph written to flamegraph.svg");
+            eprintln!("pprof flamegraph written to flamegraph.svg");
         } else {
             eprintln!("Failed to generate pprof report");
         }
     }
 }
+
+fn load(file: &str) -> P16Core {
+    match P16Core::new(file) {
+        Ok(p16) => p16,
+        Err(err) => {
+            eprintln!("{file}: {err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn disassemble(args: impl Iterator<Item = String>) {
+    let mut file = None;
+    let mut symbols = true;
+    for arg in args {
+        match arg.as_str() {
+            "--numeric" => symbols = false,
+            _ => file = Some(arg),
+        }
+    }
+    let Some(file) = file else {
+        eprintln!("{USAGE}");
+        std::process::exit(2);
+    };
+
+    let p16 = load(&file);
+    print!("{}", disasm::listing(p16.program(), symbols));
+}
@@ -1,465 +1,752 @@
-use circular_buffer::CircularBuffer;
+use std::fmt;
 
 use crate::{
-    exec::{Bit, Instruction},
+    alu,
+    exec::{self, Instruction},
+    image::{EEPROM_SIZE, Image, LoadError, PROGRAM_SIZE},
     mem::Ram,
+    peripheral::{Bus, Context, Irq, IrqLines},
+    ports::{Port, Ports},
     regs::{self},
+    stack::Stack,
+    wdt::Watchdog,
 };
 
+/// Instruction cycles spent entering an interrupt.
+const INTERRUPT_ENTRY_CYCLES: u8 = 2;
+
+/// Oscillator frequency used unless [`P16Core::set_clock_hz`] changes it.
+pub const DEFAULT_CLOCK_HZ: u32 = 20_000_000;
+
+/// What the core does when it executes an [`Instruction::Invalid`] word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InvalidOpcodePolicy {
+    /// Stop the simulation and report a [`Halt::InvalidOpcode`].
+    #[default]
+    Halt,
+    /// Execute the word as a NOP.
+    Nop,
+    /// Restart the program from the reset vector.
+    Reset,
+}
+
+/// What the core does when firmware accesses a data memory address that
+/// neither the core nor any peripheral implements. Accesses through
+/// [`P16Core::read`] and [`P16Core::write`] from outside an instruction
+/// always read 0 and ignore writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnimplementedAccessPolicy {
+    /// Read 0 and ignore writes, as the hardware does.
+    #[default]
+    ReadZero,
+    /// As `ReadZero`, but log every access; see [`P16Core::take_warnings`].
+    Warn,
+    /// Stop the simulation and report a [`Halt::UnimplementedAccess`].
+    Stop,
+}
+
+/// Source of a reset, which determines the TO and PD bits and which
+/// registers keep their contents. See [`P16Core::reset`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetKind {
+    PowerOn,
+    BrownOut,
+    /// MCLR pulled low while running.
+    Mclr,
+    /// MCLR pulled low during SLEEP.
+    MclrSleep,
+    /// Watchdog time-out while running.
+    Watchdog,
+    /// Watchdog time-out during SLEEP, which wakes the core instead of
+    /// resetting it.
+    WatchdogWake,
+}
+
+/// Reason the core stopped executing instructions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Halt {
+    InvalidOpcode {
+        pc: u16,
+        word: u16,
+    },
+    UnimplementedAccess {
+        pc: u16,
+        instruction: Instruction,
+        address: u16,
+        write: bool,
+    },
+}
+
+impl fmt::Display for Halt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Halt::InvalidOpcode { pc, word } => {
+                write!(f, "invalid opcode 0x{word:04X} at 0x{pc:04X}")
+            }
+            Halt::UnimplementedAccess {
+                pc,
+                instruction,
+                address,
+                write,
+            } => {
+                let access = if *write { "write to" } else { "read of" };
+                write!(
+                    f,
+                    "{access} unimplemented address 0x{address:03X} at 0x{pc:04X} ({instruction})"
+                )
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct P16Core {
     program: [u16; 4096],
+    id_locations: [u16; 4],
+    config: regs::Config,
+    eeprom: [u8; EEPROM_SIZE],
     file: Ram,
-    pub skip_next: bool,
-    pub stack: CircularBuffer<8, u16>,
+    halted: Option<Halt>,
+    /// Accesses logged under [`UnimplementedAccessPolicy::Warn`].
+    warnings: Vec<Halt>,
+    pub invalid_opcode_policy: InvalidOpcodePolicy,
+    pub unimplemented_access_policy: UnimplementedAccessPolicy,
+    /// Increments PTR1/PTR2 after every access through INDF1/INDF2.
+    pub pointer_post_increment: bool,
+    /// Oscillator frequency; one instruction cycle takes four clocks.
+    clock_hz: u32,
+    /// Forces the watchdog on or off regardless of the configuration word.
+    pub wdt_override: Option<bool>,
+    sleeping: bool,
+    /// Instruction cycles executed since the core was created.
+    cycles: u64,
+    /// Set by a write to PCL, which costs the instruction an extra cycle.
+    pcl_written: bool,
+    /// Address the instruction being executed was fetched from.
+    instruction_pc: u16,
+    /// Set while [`P16Core::exec_op`] runs, so that only firmware accesses
+    /// are subject to the [`UnimplementedAccessPolicy`].
+    executing: bool,
+    pub stack: Stack,
 
     pub w: u8,
     pub status: regs::Status,
     pub pc: u16,
-    tmr0: u8,
     option: regs::Option,
     fsr: u8,
-    port_a: u8,
-    port_b: u8,
-    port_c: u8,
-    port_d: u8,
     pub pclath: u8,
     pub intcon: regs::Intcon,
     pir1: regs::PIR1,
     pie1: regs::PIE1,
-    indf1: u8,
-    indf2: u8,
-    t1con: regs::T1CON,
-    tmr1: u16,
-    dan: u8,
-    dseg: u8,
-    rcsta: u8,
-    tx_reg: u8,
-    rc_reg: u8,
-    ptr1_h: u8,
-    ptr1_l: u8,
-    ptr2_l: u8,
-    ptr2_h: u8,
-
-    tmr1_prescale_counter: u8,
-    tmr0_prescale_counter: u8,
+    /// PTR1H:PTR1L and PTR2H:PTR2L, dereferenced by INDF1 and INDF2.
+    ///
+    /// 0x0000–0x01FF address data memory directly, bypassing banking.
+    /// 0x8000–0x9FFF address program memory a byte at a time and are
+    /// read-only: word `n` sits at `0x8000 + 2n`, its low eight bits at the
+    /// even address and its upper six bits at the odd one. Other values are
+    /// unimplemented.
+    ptr: [u16; 2],
+
+    wdt: Watchdog,
+    bus: Bus,
 }
 
 impl Default for P16Core {
     fn default() -> Self {
         Self {
             program: [0; 4096],
+            id_locations: [0x3FFF; 4],
+            config: Default::default(),
+            eeprom: [0xFF; EEPROM_SIZE],
             file: Default::default(),
-            skip_next: Default::default(),
-            stack: CircularBuffer::new(),
+            halted: None,
+            warnings: Vec::new(),
+            invalid_opcode_policy: Default::default(),
+            unimplemented_access_policy: Default::default(),
+            pointer_post_increment: false,
+            clock_hz: DEFAULT_CLOCK_HZ,
+            wdt_override: None,
+            sleeping: false,
+            cycles: 0,
+            pcl_written: false,
+            instruction_pc: 0,
+            executing: false,
+            stack: Default::default(),
 
             w: Default::default(),
             status: Default::default(),
             pc: Default::default(),
-            tmr0: Default::default(),
             option: Default::default(),
             fsr: Default::default(),
-            port_a: Default::default(),
-            port_b: Default::default(),
-            port_c: Default::default(),
-            port_d: Default::default(),
             pclath: Default::default(),
             intcon: Default::default(),
             pir1: Default::default(),
             pie1: Default::default(),
-            indf1: Default::default(),
-            indf2: Default::default(),
-            t1con: Default::default(),
-            tmr1: Default::default(),
-            dan: Default::default(),
-            dseg: Default::default(),
-            rcsta: Default::default(),
-            tx_reg: Default::default(),
-            rc_reg: Default::default(),
-            ptr1_h: Default::default(),
-            ptr1_l: Default::default(),
-            ptr2_l: Default::default(),
-            ptr2_h: Default::default(),
-
-            tmr0_prescale_counter: Default::default(),
-            tmr1_prescale_counter: Default::default(),
+            ptr: [0; 2],
+
+            wdt: Default::default(),
+            bus: Bus::p16core(),
         }
     }
 }
 
 impl P16Core {
-    pub fn new(file: &str) -> Self {
-        let contents = std::fs::read_to_string(file).expect("failed to read file");
-
-        let mut program = [0; 4096]; // 8K program memory
-        let mut upper_addr = 0u32;
-
-        for line in contents.lines() {
-            if !line.starts_with(':') {
-                continue;
-            }
-            let bytes = hex::decode(&line[1..]).unwrap(); // requires `hex` crate
-
-            let count = bytes[0] as usize;
-            let addr = ((bytes[1] as u16) << 8 | bytes[2] as u16) as u32;
-            let rtype = bytes[3];
-            let data = &bytes[4..4 + count];
-
-            match rtype {
-                0x00 => {
-                    let full_addr = (upper_addr << 16) + addr;
-                    for i in (0..count).step_by(2) {
-                        let lo = data[i];
-                        let hi = if i + 1 < count { data[i + 1] } else { 0 };
-                        let word = ((hi as u16) << 8) | lo as u16;
-                        program[(full_addr as usize + i) / 2] = word & 0x3FFF;
-                    }
-                }
-                0x04 => {
-                    upper_addr = ((data[0] as u32) << 8) | data[1] as u32;
-                }
-                0x01 => break, // EOF
-                _ => {}
-            }
-        }
+    /// Loads an Intel HEX or raw binary file into a freshly reset core.
+    pub fn new(file: &str) -> Result<Self, LoadError> {
+        Ok(Self::from_image(Image::from_file(file)?))
+    }
 
+    pub fn from_image(image: Image) -> Self {
         Self {
-            program,
+            program: image.program,
+            id_locations: image.id_locations,
+            config: regs::Config::new(image.config),
+            eeprom: image.eeprom,
             ..Default::default()
         }
     }
 
-    #[cfg_attr(feature = "trace", tracing::instrument)]
-    pub fn decode(word: u16) -> Instruction {
-        match (word >> 8) as u8 {
-            0b00_0000..=0b00_1111 => match (word >> 8) as u8 {
-                0b00_0000 => match (word & 0xff) as u8 {
-                    0b0000_0000 | 0b0010_0000 | 0b0100_0000 | 0b0110_0000 => Instruction::NOP,
-                    0b0000_1000 => Instruction::RETURN,
-                    0b0110_0100 => {
-                        panic!("CLRWDT OPCODE {word}")
-                    }
-                    0b0000_1001 => Instruction::RETFIE,
-                    0b0110_0011 => {
-                        panic!("SLEEP OPCODE {word}")
-                    }
-                    0b1000_0000..=0b1111_1111 => Instruction::MOVWF {
-                        reg: (word & 0b0111_1111) as u8,
-                    },
-                    0b0000_0001..=0b0000_0111
-                    | 0b0000_1010..=0b0001_1111
-                    | 0b0010_0001..=0b0011_1111
-                    | 0b0100_0000..=0b0101_1111
-                    | 0b0110_0001..=0b0110_0010
-                    | 0b0110_0101..=0b0111_1111 => {
-                        panic!("UNKNOWN OPCODE {word}")
-                    }
-                },
-                0b00_0001 => match (word >> 7) & 1 == 0 {
-                    true => Instruction::CLRW,
-                    false => Instruction::CLRF {
-                        reg: (word & 0b0111_1111) as u8,
-                    },
-                },
-                0b00_0010 => Instruction::SUBWF {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b00_0011 => Instruction::DECF {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b00_0100 => Instruction::IORWF {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b00_0101 => Instruction::ANDWF {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b00_0110 => Instruction::XORWF {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b00_0111 => Instruction::ADDWF {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b00_1000 => Instruction::MOVF {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b00_1001 => Instruction::COMF {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b00_1010 => Instruction::INCF {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b00_1011 => Instruction::DECFSZ {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b00_1100 => Instruction::RRF {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b00_1101 => Instruction::RLF {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b00_1110 => Instruction::SWAPF {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b00_1111 => Instruction::INCFSZ {
-                    reg: (word & 0b0111_1111) as u8,
-                    dest: (word >> 7) & 1 == 1,
-                },
-                0b01_0000..=0b11_1111 => panic!("Unexpected OPCODE in 0b00_... section"),
-                0b0100_0000..=0b1111_1111 => unreachable!(),
-            },
-            0b01_0000..=0b01_1111 => {
-                let reg = (word & 0b0111_1111) as u8;
-                let bit = Bit::try_from((word >> 7) & 0b0111).unwrap();
-                match word >> 10 {
-                    0b01_00 => Instruction::BCF { reg, bit },
-                    0b01_01 => Instruction::BSF { reg, bit },
-                    0b01_10 => Instruction::BTFSC { reg, bit },
-                    0b01_11 => Instruction::BTFSS { reg, bit },
-                    _ => unreachable!(),
-                }
-            }
-            0b10_0000..=0b10_1111 => {
-                let lit = word & 0b0111_1111_1111;
-                match (word >> 11) & 1 == 0 {
-                    true => Instruction::CALL { lit },
-                    false => Instruction::GOTO { lit },
-                }
-            }
-            0b11_0000..=0b11_1111 => match ((word >> 8) & 0b1111) as u8 {
-                0b1001 => Instruction::ANDLW {
-                    lit: (word & 0xff) as u8,
-                },
-                0b1110..=0b1111 => Instruction::ADDLW {
-                    lit: (word & 0xff) as u8,
-                },
-                0b1000 => Instruction::IORLW {
-                    lit: (word & 0xff) as u8,
-                },
-                0b0000..=0b0011 => Instruction::MOVLW {
-                    lit: (word & 0xff) as u8,
-                },
-                0b0100..=0b0111 => Instruction::RETLW {
-                    lit: (word & 0xff) as u8,
-                },
-                0b1100..=0b1101 => Instruction::SUBLW {
-                    lit: (word & 0xff) as u8,
-                },
-                0b1010 => Instruction::XORLW {
-                    lit: (word & 0xff) as u8,
-                },
-                0b1011 => panic!("UNKNOWN OPCODE {word}"),
-                0b0001_0000..=0b1111_1111 => unreachable!(),
-            },
-            0b0100_0000..=0b1111_1111 => unreachable!(),
+    /// Snapshot of program memory, configuration and data EEPROM, including
+    /// any changes made since loading.
+    pub fn image(&self) -> Image {
+        Image {
+            program: self.program,
+            id_locations: self.id_locations,
+            config: self.config.value(),
+            eeprom: self.eeprom,
         }
     }
 
-    pub fn get_next_op(&mut self) -> u16 {
-        let mut int = false;
-        #[cfg(feature = "flame")]
-        flame::start("inc_tmr0");
+    /// What a device programmer reads back from the part. While CP is set
+    /// program memory reads as zeros, and while CPD is set data EEPROM does;
+    /// the ID locations and the configuration word are always readable.
+    pub fn read_back(&self) -> Image {
+        let mut image = self.image();
+        if self.config.code_protected() {
+            image.program = [0; 4096];
+        }
+        if self.config.data_protected() {
+            image.eeprom = [0; EEPROM_SIZE];
+        }
+        image
+    }
+
+    /// Replaces program memory, ID locations, config word and EEPROM with
+    /// `image`, then applies a power-on reset and restarts the cycle count.
+    /// Attached peripherals, host handles and policies are kept.
+    pub fn load_image(&mut self, image: Image) {
+        self.program = image.program;
+        self.id_locations = image.id_locations;
+        self.config = regs::Config::new(image.config);
+        self.eeprom = image.eeprom;
+        self.cycles = 0;
+        self.reset(ResetKind::PowerOn);
+    }
+
+    /// Program memory as loaded, one 14-bit word per address.
+    pub fn program(&self) -> &[u16; 4096] {
+        &self.program
+    }
+
+    pub fn program_mut(&mut self) -> &mut [u16; 4096] {
+        &mut self.program
+    }
+
+    pub fn config(&self) -> &regs::Config {
+        &self.config
+    }
+
+    /// Data EEPROM, preloaded from the image when the program is loaded.
+    pub fn eeprom(&self) -> &[u8; EEPROM_SIZE] {
+        &self.eeprom
+    }
+
+    pub fn eeprom_mut(&mut self) -> &mut [u8; EEPROM_SIZE] {
+        &mut self.eeprom
+    }
+
+    /// Why the core stopped, if it did. A halted core ignores [`P16Core::step`].
+    pub fn halted(&self) -> Option<&Halt> {
+        self.halted.as_ref()
+    }
+
+    /// Unimplemented accesses logged under [`UnimplementedAccessPolicy::Warn`],
+    /// each described as the [`Halt`] that `Stop` would have reported.
+    pub fn warnings(&self) -> &[Halt] {
+        &self.warnings
+    }
+
+    /// Returns the logged warnings and clears the log.
+    pub fn take_warnings(&mut self) -> Vec<Halt> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Clears the halt state so execution continues at the current PC.
+    pub fn resume(&mut self) {
+        self.halted = None;
+    }
+
+    /// Applies a reset of the given kind, loading each register with the
+    /// value the datasheet lists for it.
+    ///
+    /// Every kind except [`ResetKind::WatchdogWake`] restarts at the reset
+    /// vector with banking, PCLATH, INTCON, PIR1/PIE1 and OPTION_REG cleared
+    /// to their defaults, and resets each attached peripheral. Power-on and
+    /// brown-out resets also clear W, data RAM, RBIF and the remaining
+    /// registers, which the hardware leaves undefined; the other resets
+    /// preserve them.
+    /// Program memory, EEPROM and the simulator settings are never touched.
+    pub fn reset(&mut self, kind: ResetKind) {
+        if kind == ResetKind::WatchdogWake {
+            // Not a real reset: execution continues after the SLEEP.
+            self.sleeping = false;
+            self.status.to = false;
+            self.status.pd = false;
+            return;
+        }
+
+        let mut stack = std::mem::take(&mut self.stack);
+        stack.reset();
+        let mut bus = std::mem::take(&mut self.bus);
+        bus.reset(kind);
+        let mut core = Self {
+            program: self.program,
+            id_locations: self.id_locations,
+            config: self.config.clone(),
+            eeprom: self.eeprom,
+            invalid_opcode_policy: self.invalid_opcode_policy,
+            unimplemented_access_policy: self.unimplemented_access_policy,
+            pointer_post_increment: self.pointer_post_increment,
+            clock_hz: self.clock_hz,
+            wdt_override: self.wdt_override,
+            cycles: self.cycles,
+            warnings: std::mem::take(&mut self.warnings),
+            stack,
+            bus,
+            ..Default::default()
+        };
+
+        if !matches!(kind, ResetKind::PowerOn | ResetKind::BrownOut) {
+            core.file = self.file.clone();
+            core.w = self.w;
+            core.status.z = self.status.z;
+            core.status.dc = self.status.dc;
+            core.status.c = self.status.c;
+            core.fsr = self.fsr;
+            core.ptr = self.ptr;
+            core.intcon.rbif = self.intcon.rbif;
+        }
+
+        (core.status.to, core.status.pd) = match kind {
+            ResetKind::PowerOn | ResetKind::BrownOut => (true, true),
+            ResetKind::Mclr => (self.status.to, self.status.pd),
+            ResetKind::MclrSleep => (true, false),
+            ResetKind::Watchdog => (false, true),
+            ResetKind::WatchdogWake => unreachable!(),
+        };
+        *self = core;
+    }
+
+    pub fn watchdog_enabled(&self) -> bool {
+        self.wdt_override.unwrap_or(self.config.watchdog_enabled())
+    }
+
+    /// OPTION_REG prescaler ratio applied to the watchdog time-out.
+    fn wdt_postscale(&self) -> u16 {
+        // The prescaler acts as the WDT postscaler when PSA is set.
+        if self.option.psa {
+            1 << (self.option.value() & 0b00000111)
+        } else {
+            1
+        }
+    }
+
+    pub fn clock_hz(&self) -> u32 {
+        self.clock_hz
+    }
+
+    /// Sets the oscillator frequency, which times the watchdog and the
+    /// peripherals.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hz` is 0.
+    pub fn set_clock_hz(&mut self, hz: u32) {
+        assert!(hz > 0, "clock frequency must be non-zero");
+        self.clock_hz = hz;
+    }
+
+    pub fn sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    /// Whether an enabled interrupt flag is set, regardless of GIE. Sources
+    /// in PIE1/PIR1 are additionally gated by PEIE.
+    fn wake_pending(&self) -> bool {
+        (self.intcon.tmr0ie && self.intcon.tmr0if)
+            || (self.intcon.inte && self.intcon.intf)
+            || (self.intcon.rbie && self.intcon.rbif)
+            || (self.intcon.peie && self.pie1.value() & self.pir1.value() != 0)
+    }
+
+    /// Whether the core will vector to 0x0004 once the current instruction
+    /// completes.
+    pub fn interrupt_pending(&self) -> bool {
+        self.intcon.gie && self.wake_pending()
+    }
+
+    /// Advances one instruction cycle while asleep. Only the watchdog keeps
+    /// running; it or any enabled interrupt wakes the core. With GIE set the
+    /// instruction after SLEEP executes before the interrupt is taken.
+    fn sleep_cycle(&mut self) {
+        self.cycles += 1;
+        if self.watchdog_enabled() && self.wdt.tick(self.cycle_ns(), self.wdt_postscale()) {
+            self.reset(ResetKind::WatchdogWake);
+        } else if self.wake_pending() {
+            self.sleeping = false;
+        }
+    }
+
+    /// Skips ahead to the point where the sleeping core wakes up, instead of
+    /// stepping through every idle cycle.
+    ///
+    /// Returns the number of instruction cycles skipped, or `None` when only
+    /// an external event ([`P16Core::drive_pin`]) can wake the core.
+    pub fn sleep_until_wake(&mut self) -> Option<u64> {
+        if !self.sleeping {
+            return Some(0);
+        }
+        if self.wake_pending() {
+            self.sleep_cycle();
+            return Some(1);
+        }
+        if !self.watchdog_enabled() {
+            return None;
+        }
+
+        let cycle_ns = self.cycle_ns();
+        let cycles = self
+            .wdt
+            .remaining_ns(self.wdt_postscale())
+            .div_ceil(cycle_ns);
+        self.wdt.tick((cycles - 1) * cycle_ns, self.wdt_postscale());
+        self.cycles += cycles - 1;
+        self.sleep_cycle();
+        Some(cycles)
+    }
+
+    /// Drives `pin` of `port` from outside, or releases it with `None`. An
+    /// RB0/INT edge in the direction selected by OPTION_REG INTEDG sets INTF
+    /// and a level change on an RB7:RB4 input sets RBIF right away, so this
+    /// also wakes a sleeping core. Forwards to [`Ports::drive`], which
+    /// rejects pins past 7.
+    pub fn drive_pin(&mut self, port: Port, pin: u8, level: Option<bool>) {
+        let Some(ports) = self.bus.get_mut::<Ports>() else {
+            return;
+        };
+        ports.drive(port, pin, level);
+        let sensed = ports.sense(&self.option);
+        self.intcon.intf |= sensed.int_edge;
+        self.intcon.rbif |= sensed.port_change;
+    }
+
+    /// The level on `pin` of `port`: the latch while it is an output,
+    /// otherwise the level driven from outside or the PORTB pull-up.
+    /// Forwards to [`Ports::output`], which rejects pins past 7, and to
+    /// [`Ports::levels`] while the pin is an input.
+    pub fn pin(&self, port: Port, pin: u8) -> bool {
+        self.bus.get::<Ports>().is_some_and(|ports| {
+            ports
+                .output(port, pin)
+                .unwrap_or_else(|| ports.levels(port, &self.option) & 1 << pin != 0)
+        })
+    }
+
+    /// Drives the RB0/INT pin.
+    pub fn set_int_pin(&mut self, level: bool) {
+        self.drive_pin(Port::B, 0, Some(level));
+    }
+
+    /// Drives the RB7:RB4 pins to the upper nibble of `value`.
+    pub fn set_port_b_inputs(&mut self, value: u8) {
+        for pin in 4..8 {
+            self.drive_pin(Port::B, pin, Some(value & 1 << pin != 0));
+        }
+    }
+
+    pub fn bus(&self) -> &Bus {
+        &self.bus
+    }
+
+    /// The attached peripherals, e.g. to attach a custom one or to reach a
+    /// built-in model with [`Bus::get_mut`].
+    pub fn bus_mut(&mut self) -> &mut Bus {
+        &mut self.bus
+    }
+
+    /// Reads a peripheral register, or `None` if nothing decodes `address`.
+    fn bus_read(&mut self, address: u16) -> Option<u8> {
+        let mut lines = IrqLines::default();
+        let value = self.bus.read(
+            address,
+            &mut Context::new(&self.option, self.clock_hz, &mut lines),
+        );
+        self.apply_irq_lines(lines);
+        value
+    }
+
+    /// Writes a peripheral register, returning `false` if nothing decodes
+    /// `address`.
+    fn bus_write(&mut self, address: u16, value: u8) -> bool {
+        let mut lines = IrqLines::default();
+        let decoded = self.bus.write(
+            address,
+            value,
+            &mut Context::new(&self.option, self.clock_hz, &mut lines),
+        );
+        self.apply_irq_lines(lines);
+        decoded
+    }
+
+    /// Applies the [`UnimplementedAccessPolicy`] to an access of `address`.
+    fn unimplemented_access(&mut self, address: u16, write: bool) {
+        if !self.executing
+            || self.unimplemented_access_policy == UnimplementedAccessPolicy::ReadZero
         {
-            self.tmr0_prescale_counter += 1;
-            let prescale = 1 << (self.option.value() & 0b00000111);
-            if self.tmr0_prescale_counter == prescale {
-                self.tmr0_prescale_counter = 0;
-                let (v, o) = self.tmr0.overflowing_add(1);
-                self.tmr0 = v;
-                self.intcon.tmr0if = true;
-                if o && self.intcon.tmr0ie {
-                    int = true;
-                }
+            return;
+        }
+        let pc = self.instruction_pc;
+        let halt = Halt::UnimplementedAccess {
+            pc,
+            instruction: exec::decode(self.program[pc as usize % PROGRAM_SIZE]),
+            address,
+            write,
+        };
+        if self.unimplemented_access_policy == UnimplementedAccessPolicy::Stop {
+            self.halted.get_or_insert(halt);
+        } else {
+            self.warnings.push(halt);
+        }
+    }
+
+    /// Updates INTCON and PIR1 with the flags peripherals raised or lowered.
+    fn apply_irq_lines(&mut self, lines: IrqLines) {
+        for (irq, flag) in [
+            (Irq::Tmr0, &mut self.intcon.tmr0if),
+            (Irq::Int, &mut self.intcon.intf),
+            (Irq::PortChange, &mut self.intcon.rbif),
+        ] {
+            if lines.raised(irq) {
+                *flag = true;
+            } else if lines.lowered(irq) {
+                *flag = false;
             }
         }
-        #[cfg(feature = "flame")]
-        flame::end("inc_tmr0");
-        
-        #[cfg(feature = "flame")]
-        flame::start("inc_tmr1");
-        if self.t1con.tmr1on {
-            self.tmr1_prescale_counter += 1;
-            let v = self.t1con.value();
-            if self.tmr1_prescale_counter == 1 << ((v & 0b00110000) >> 4) {
-                self.tmr1_prescale_counter = 0;
-                let (v, o) = self.tmr1.overflowing_add(1);
-                self.tmr1 = v;
-                self.pir1.tmr1if = true;
-                if o && self.pie1.tmr1ie {
-                    int = true;
-                }
+
+        let mut pir1 = self.pir1.value();
+        for irq in [
+            Irq::Tmr1,
+            Irq::Tmr2,
+            Irq::Ccp1,
+            Irq::Ssp,
+            Irq::Tx,
+            Irq::Rc,
+            Irq::Ad,
+            Irq::Psp,
+        ] {
+            let bit = 1 << irq.pir1_bit().unwrap_or_default();
+            if lines.raised(irq) {
+                pir1 |= bit;
+            } else if lines.lowered(irq) {
+                pir1 &= !bit;
             }
         }
+        self.pir1.set(pir1);
+    }
+
+    /// Duration of one instruction cycle in nanoseconds.
+    fn cycle_ns(&self) -> u64 {
+        4_000_000_000 / self.clock_hz as u64
+    }
+
+    /// Instruction cycles executed so far, including cycles spent asleep.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Fetches, decodes and executes a single instruction, advancing the
+    /// timers by the cycles it takes, then takes a pending interrupt.
+    /// Returns the number of instruction cycles consumed, including those of
+    /// the interrupt entry.
+    pub fn step(&mut self) -> u8 {
+        if self.halted.is_some() {
+            return 0;
+        }
+        if self.sleeping {
+            self.sleep_cycle();
+            return 1;
+        }
+
+        #[cfg(feature = "flame")]
+        flame::start("get_next_op");
+        let op = self.get_next_op();
         #[cfg(feature = "flame")]
-        flame::end("inc_tmr1");
+        flame::end("get_next_op");
 
         #[cfg(feature = "flame")]
-        flame::start("intterupt");
-        if int {
-            self.intterupt();
-        }
+        flame::start("decode");
+        let instruction = exec::decode(op);
         #[cfg(feature = "flame")]
-        flame::end("intterupt");
+        flame::end("decode");
 
         #[cfg(feature = "flame")]
-        flame::start("skip");
-        let op = if self.skip_next {
-            0
-        } else {
-            self.program[(self.pc % 4096) as usize]
-        };
+        flame::start("exec_op");
+        let cycles = self.exec_op(instruction);
+        #[cfg(feature = "flame")]
+        flame::end("exec_op");
+
+        for _ in 0..cycles {
+            self.tick();
+        }
+
+        if self.halted.is_none() && !self.sleeping && self.interrupt_pending() {
+            #[cfg(feature = "flame")]
+            flame::start("interrupt");
+            self.interrupt();
+            #[cfg(feature = "flame")]
+            flame::end("interrupt");
+            return cycles + INTERRUPT_ENTRY_CYCLES;
+        }
+        cycles
+    }
+
+    /// Advances the watchdog and timers by one instruction cycle.
+    fn tick(&mut self) {
+        self.cycles += 1;
+
+        #[cfg(feature = "flame")]
+        flame::start("wdt");
+        if self.watchdog_enabled() && self.wdt.tick(self.cycle_ns(), self.wdt_postscale()) {
+            self.reset(ResetKind::Watchdog);
+        }
         #[cfg(feature = "flame")]
-        flame::end("skip");
+        flame::end("wdt");
 
         #[cfg(feature = "flame")]
-        flame::start("pc");
-        (self.pc, _) = self.pc.overflowing_add(1);
-        self.skip_next = false;
+        flame::start("peripherals");
+        let mut lines = IrqLines::default();
+        self.bus
+            .tick(&mut Context::new(&self.option, self.clock_hz, &mut lines));
+        self.apply_irq_lines(lines);
         #[cfg(feature = "flame")]
-        flame::end("pc");
+        flame::end("peripherals");
+    }
+
+    pub fn get_next_op(&mut self) -> u16 {
+        self.instruction_pc = self.pc;
+        let op = self.program[(self.pc % 4096) as usize];
+        (self.pc, _) = self.pc.overflowing_add(1);
         op
     }
 
+    /// Writes an ALU result to W or to `reg` and applies its flags. The
+    /// flags are applied last, so they win when `reg` is STATUS.
+    fn store(&mut self, reg: u8, dest: bool, outcome: alu::Outcome) {
+        if dest {
+            self.write(reg as u16, outcome.result);
+            self.apply_flags(outcome);
+        } else {
+            self.store_w(outcome);
+        }
+    }
+
+    fn store_w(&mut self, outcome: alu::Outcome) {
+        self.w = outcome.result;
+        self.apply_flags(outcome);
+    }
+
+    fn apply_flags(&mut self, outcome: alu::Outcome) {
+        if let Some(z) = outcome.z {
+            self.status.z = z;
+        }
+        if let Some(dc) = outcome.dc {
+            self.status.dc = dc;
+        }
+        if let Some(c) = outcome.c {
+            self.status.c = c;
+        }
+    }
+
     #[cfg_attr(feature = "trace", tracing::instrument(skip(self)))]
-    pub fn exec_op(&mut self, instruction: Instruction) {
+    pub fn exec_op(&mut self, instruction: Instruction) -> u8 {
+        self.executing = true;
+        let mut skip = false;
         match instruction {
             Instruction::ADDWF { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("ADDWF");
-                let b = self.read(reg as u16);
-                let (result, c) = self.w.overflowing_add(b);
-
-                if dest {
-                    self.write(reg as u16, result);
-                } else {
-                    self.w = result;
-                }
-
-                self.status.z = result == 0;
-                self.status.c = c;
-                self.status.dc = ((self.w & 0x0F) + (b & 0x0F)) > 0x0F;
+                let f = self.read(reg as u16);
+                self.store(reg, dest, alu::add(self.w, f));
             }
             Instruction::ANDWF { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("ANDWF");
-                let result = self.w & self.read(reg as u16);
-
-                if dest {
-                    self.write(reg as u16, result);
-                } else {
-                    self.w = result;
-                }
-
-                self.status.z = self.w == 0;
+                let f = self.read(reg as u16);
+                self.store(reg, dest, alu::and(self.w, f));
             }
             Instruction::CLRF { reg } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("CLRF");
-                self.write(reg as u16, 0);
-                self.status.z = true;
+                self.store(reg, true, alu::clear());
             }
             Instruction::CLRW => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("CLRW");
-                self.w = 0;
-                self.status.z = true;
+                self.store_w(alu::clear());
             }
             Instruction::COMF { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("COMF");
-                let result = !self.read(reg as u16);
-                if dest {
-                    self.write(reg as u16, result);
-                } else {
-                    self.w = result;
-                }
-
-                self.status.z = result == 0;
+                let f = self.read(reg as u16);
+                self.store(reg, dest, alu::complement(f));
             }
             Instruction::DECF { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("DECF");
-                let result = self.read(reg as u16) - 1;
-                if dest {
-                    self.write(reg as u16, result);
-                } else {
-                    self.w = result;
-                }
-
-                self.status.z = result == 0;
+                let f = self.read(reg as u16);
+                self.store(reg, dest, alu::decrement(f));
             }
             Instruction::DECFSZ { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("DECFSZ");
-                let result = self.read(reg as u16) - 1;
-                if result == 0 {
-                    self.skip_next = true;
-                }
-                if dest {
-                    self.write(reg as u16, result);
-                } else {
-                    self.w = result;
-                }
+                let outcome = alu::decrement_skip(self.read(reg as u16));
+                skip = outcome.result == 0;
+                self.store(reg, dest, outcome);
             }
             Instruction::INCF { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("INCF");
-                let result = self.read(reg as u16) + 1;
-                if dest {
-                    self.write(reg as u16, result);
-                } else {
-                    self.w = result;
-                }
-
-                self.status.z = result == 0;
+                let f = self.read(reg as u16);
+                self.store(reg, dest, alu::increment(f));
             }
             Instruction::INCFSZ { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("INCFSZ");
-                let result = self.read(reg as u16) + 1;
-                if result == 0 {
-                    self.skip_next = true;
-                }
-                if dest {
-                    self.write(reg as u16, result);
-                } else {
-                    self.w = result;
-                }
+                let outcome = alu::increment_skip(self.read(reg as u16));
+                skip = outcome.result == 0;
+                self.store(reg, dest, outcome);
             }
             Instruction::IORWF { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("IORWF");
-                let result = self.w | self.read(reg as u16);
-                if dest {
-                    self.write(reg as u16, result);
-                } else {
-                    self.w = result;
-                }
-
-                self.status.z = result == 0;
+                let f = self.read(reg as u16);
+                self.store(reg, dest, alu::or(self.w, f));
             }
             Instruction::MOVF { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("MOVF");
                 let f = self.read(reg as u16);
-
-                if dest {
-                    self.write(reg as u16, f);
-                } else {
-                    self.w = f;
-                }
+                self.store(reg, dest, alu::mov(f));
             }
             Instruction::MOVWF { reg } => {
                 #[cfg(feature = "flame")]
@@ -473,76 +760,32 @@ impl P16Core {
             Instruction::RLF { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("RLF");
-                let v = self.read(reg as u16);
-
-                let c = v >> 7;
-                let result = (v << 1) | self.status.c as u8;
-
-                if dest {
-                    self.write(reg as u16, result);
-                } else {
-                    self.w = v;
-                }
-
-                self.status.c = c == 1;
+                let f = self.read(reg as u16);
+                self.store(reg, dest, alu::rotate_left(f, self.status.c));
             }
             Instruction::RRF { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("RRF");
-                let v = self.read(reg as u16);
-
-                let c = v & 1;
-                let result = (v >> 1) | ((self.status.c as u8) << 7);
-
-                if dest {
-                    self.write(reg as u16, result);
-                } else {
-                    self.w = result;
-                }
-
-                self.status.c = c == 1;
+                let f = self.read(reg as u16);
+                self.store(reg, dest, alu::rotate_right(f, self.status.c));
             }
             Instruction::SUBWF { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("SUBWF");
                 let f = self.read(reg as u16);
-                let (result, c) = f.overflowing_sub(self.w);
-
-                if dest {
-                    self.write(reg as u16, result);
-                } else {
-                    self.w = result;
-                }
-
-                self.status.z = result == 0;
-                self.status.c = c;
-                self.status.dc = (f & 0x0F) >= (self.w & 0x0F);
+                self.store(reg, dest, alu::sub(f, self.w));
             }
             Instruction::SWAPF { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("SWAPF");
                 let f = self.read(reg as u16);
-                let result = ((f & 0xF0) >> 4) | ((f & 0xF) << 4);
-
-                if dest {
-                    self.write(reg as u16, result);
-                } else {
-                    self.w = result;
-                }
+                self.store(reg, dest, alu::swap(f));
             }
             Instruction::XORWF { reg, dest } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("XORWF");
                 let f = self.read(reg as u16);
-                let result = self.w ^ f;
-
-                if dest {
-                    self.write(reg as u16, result);
-                } else {
-                    self.w = result;
-                }
-
-                self.status.z = result == 0;
+                self.store(reg, dest, alu::xor(self.w, f));
             }
             Instruction::BCF { reg, bit } => {
                 #[cfg(feature = "flame")]
@@ -559,52 +802,45 @@ impl P16Core {
             Instruction::BTFSC { reg, bit } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("BTFSC");
-                let bit = self.read(reg as u16) & (1u8 << bit) == 1;
-                if !bit {
-                    self.skip_next = true;
-                }
+                skip = !alu::bit_set(self.read(reg as u16), bit.as_u8());
             }
             Instruction::BTFSS { reg, bit } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("BTFSS");
-                let bit = self.read(reg as u16) & (1u8 << bit) == 1;
-                if bit {
-                    self.skip_next = true;
-                }
+                skip = alu::bit_set(self.read(reg as u16), bit.as_u8());
             }
             Instruction::ADDLW { lit } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("ADDLW");
-                let (result, c) = self.w.overflowing_add(lit);
-
-                self.w = result;
-
-                self.status.z = result == 0;
-                self.status.c = c;
-                self.status.dc = ((self.w & 0x0F) + (lit & 0x0F)) > 0x0F;
+                self.store_w(alu::add(self.w, lit));
             }
             Instruction::ANDLW { lit } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("ANDLW");
-                self.w &= lit;
-                self.status.z = self.w == 0;
+                self.store_w(alu::and(self.w, lit));
             }
             Instruction::CALL { lit } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("CALL");
-                self.stack.push_front(self.pc);
-                self.pc = (self.pclath as u16 & 0x18 << 7) | lit & 0x3FF;
+                self.stack.push(self.pc, self.pc.wrapping_sub(1) & 0x1FFF);
+                self.pc = ((self.pclath as u16 & 0x18) << 8) | lit;
+            }
+            Instruction::CLRWDT => {
+                #[cfg(feature = "flame")]
+                flame::start_guard("CLRWDT");
+                self.wdt.clear();
+                self.status.to = true;
+                self.status.pd = true;
             }
             Instruction::GOTO { lit } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("GOTO");
-                self.pc = (self.pclath as u16 & 0x18 << 7) | lit & 0x3FF;
+                self.pc = ((self.pclath as u16 & 0x18) << 8) | lit;
             }
             Instruction::IORLW { lit } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("IORLW");
-                self.w |= lit;
-                self.status.z = self.w == 0;
+                self.store_w(alu::or(self.w, lit));
             }
             Instruction::MOVLW { lit } => {
                 #[cfg(feature = "flame")]
@@ -614,72 +850,168 @@ impl P16Core {
             Instruction::RETFIE => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("RETFIE");
-                self.pc = self.stack.pop_front().unwrap();
+                self.pc = self.stack.pop(self.pc.wrapping_sub(1) & 0x1FFF);
                 self.intcon.gie = true;
             }
             Instruction::RETLW { lit } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("RETLW");
                 self.w = lit;
-                self.pc = self.stack.pop_front().unwrap();
+                self.pc = self.stack.pop(self.pc.wrapping_sub(1) & 0x1FFF);
             }
             Instruction::RETURN => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("RETURN");
-                self.pc = self.stack.pop_front().unwrap();
+                self.pc = self.stack.pop(self.pc.wrapping_sub(1) & 0x1FFF);
+            }
+            Instruction::SLEEP => {
+                #[cfg(feature = "flame")]
+                flame::start_guard("SLEEP");
+                // With an enabled interrupt already pending SLEEP is a NOP.
+                if !self.wake_pending() {
+                    self.wdt.clear();
+                    self.status.to = true;
+                    self.status.pd = false;
+                    self.sleeping = true;
+                }
             }
             Instruction::SUBLW { lit } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("SUBLW");
-                let (result, c) = lit.overflowing_sub(self.w);
-
-                self.w = result;
-
-                self.status.z = result == 0;
-                self.status.c = c;
-                self.status.dc = (lit & 0x0F) >= (self.w & 0x0F);
+                self.store_w(alu::sub(lit, self.w));
             }
             Instruction::XORLW { lit } => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("XORLW");
-                self.w ^= lit;
-                self.status.z = self.w == 0;
+                self.store_w(alu::xor(self.w, lit));
+            }
+            Instruction::Invalid(word) => {
+                #[cfg(feature = "flame")]
+                flame::start_guard("Invalid");
+                match self.invalid_opcode_policy {
+                    InvalidOpcodePolicy::Halt => {
+                        self.pc = self.pc.wrapping_sub(1) & 0x1FFF;
+                        self.halted = Some(Halt::InvalidOpcode { pc: self.pc, word });
+                    }
+                    InvalidOpcodePolicy::Nop => {}
+                    InvalidOpcodePolicy::Reset => self.reset(ResetKind::Mclr),
+                }
+            }
+        }
+        self.executing = false;
+
+        // A taken skip discards the prefetched instruction, as does any
+        // change of program flow.
+        let mut cycles = match instruction {
+            Instruction::CALL { .. }
+            | Instruction::GOTO { .. }
+            | Instruction::RETFIE
+            | Instruction::RETLW { .. }
+            | Instruction::RETURN => 2,
+            _ => 1,
+        };
+        if skip {
+            self.pc = self.pc.wrapping_add(1);
+            cycles = 2;
+        }
+        if std::mem::take(&mut self.pcl_written) {
+            cycles = 2;
+        }
+        // Like an invalid opcode, an access that stops the core leaves PC on
+        // the faulting instruction.
+        if let Some(Halt::UnimplementedAccess { pc, .. }) = self.halted {
+            self.pc = pc;
+        }
+        cycles
+    }
+
+    /// Full 9-bit data memory address of a register operand. Addresses up to
+    /// 0x7F are relative to the bank selected by RP1:RP0, as in an
+    /// instruction; larger ones are taken as absolute.
+    fn bank_address(&self, address: u16) -> u16 {
+        if address > 0x7f {
+            address
+        } else {
+            (((self.status.rp1 as u16) << 1 | (self.status.rp0 as u16)) << 7) | address
+        }
+    }
+
+    /// The 9-bit address INDF refers to: IRP selects the bank pair and FSR
+    /// the register within it. RP1:RP0 play no part.
+    fn indirect_address(&self) -> u16 {
+        ((self.status.irp as u16) << 8) | self.fsr as u16
+    }
+
+    /// Reads through PTR1 (`n = 0`) or PTR2 (`n = 1`) for INDF1/INDF2.
+    ///
+    /// See [`P16Core::ptr`] for the address map. Pointing an INDF register
+    /// at itself or another INDF reads 0.
+    fn read_pointer(&mut self, n: usize) -> u8 {
+        let ptr = self.ptr[n];
+        let value = match ptr {
+            0x0000..=0x01FF if is_indirect_port(ptr) => 0,
+            0x0000..=0x01FF => self.read_data(ptr),
+            0x8000..=0x9FFF => {
+                let word = self.program[(ptr as usize - 0x8000) / 2];
+                if ptr & 1 == 0 {
+                    word as u8
+                } else {
+                    (word >> 8) as u8
+                }
             }
+            _ => {
+                self.unimplemented_access(ptr, false);
+                0
+            }
+        };
+        self.advance_pointer(n);
+        value
+    }
+
+    /// Writes through PTR1 or PTR2. Program memory is read-only.
+    fn write_pointer(&mut self, n: usize, value: u8) {
+        let ptr = self.ptr[n];
+        match ptr {
+            0x0000..=0x01FF if is_indirect_port(ptr) => {}
+            0x0000..=0x01FF => self.write_data(ptr, value),
+            0x8000..=0x9FFF => {}
+            _ => self.unimplemented_access(ptr, true),
         }
+        self.advance_pointer(n);
     }
 
+    fn advance_pointer(&mut self, n: usize) {
+        if self.pointer_post_increment {
+            self.ptr[n] = self.ptr[n].wrapping_add(1);
+        }
+    }
+
+    /// Writes a register. See [`P16Core::read`] for how `address` is resolved.
     #[cfg_attr(feature = "trace", tracing::instrument(skip(self)))]
     pub fn write(&mut self, address: u16, value: u8) {
+        self.write_data(self.bank_address(address), value);
+    }
+
+    fn write_data(&mut self, address: u16, value: u8) {
         #[cfg(feature = "flame")]
         flame::start("write");
-        let address =
-            (((self.status.rp1 as u16) << 1 | (self.status.rp0 as u16)) << 8) | (address as u16);
         match address {
             0x000 | 0x080 | 0x100 | 0x180 => {
-                if self.fsr != 0 {
-                    self.write(
-                        (if self.status.irp == true { 1 } else { 0 }) << 8 | (self.fsr as u16),
-                        value,
-                    );
+                // Writing INDF through INDF is a no-op.
+                let target = self.indirect_address();
+                if target & 0x7F != 0 {
+                    self.write_data(target, value);
                 }
             } // Indirect addr
-            0x001 | 0x101 => {
-                self.tmr0 = value;
-                self.tmr0_prescale_counter = 0;
-            } // TMR0
             0x081 | 0x181 => self.option.set(value), // OPTION_REG
             0x002 | 0x082 | 0x102 | 0x182 => {
                 self.pc = ((self.pclath as u16 & 0x1F) << 8) | value as u16;
+                self.pcl_written = true;
             } // PCL
             0x003 | 0x083 | 0x103 | 0x183 => self
                 .status
                 .set((self.status.value() & 0b00011000) | (value & 0b11100111)), // STATUS
             0x004 | 0x084 | 0x104 | 0x184 => self.fsr = value, // FSR
-            0x005 => self.port_a = value,            // PORTA
-            0x006 => self.port_b = value,            // PORTB
-            0x007 => self.port_c = value,            // PORTC
-            0x008 => self.port_d = value,            // PORTD
-            0x009 => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
             0x00A => self.pclath = value,            // PCLATH
             0x00B | 0x08B | 0x10B | 0x18B => self.intcon.set(value), // INTCON
             0x00C => {
@@ -687,31 +1019,16 @@ impl P16Core {
                     .set((self.pir1.value() & 0b00110000) | (value & 0b11001111));
             } // PIR1
             0x08C => self.pie1.set(value),           // PIE1
-            0x00D => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x00E | 0x08E | 0x10E | 0x18E => self.indf1 = value, // INDF1
-            0x00F | 0x08F | 0x10F | 0x18F => self.indf2 = value, // INDF2
-            0x010 => self.t1con.set(value),          // T1CON
-            0x011 => self.tmr1 = (self.tmr1 & 0xff00) | (value as u16), // T1L
-            0x012 => self.tmr1 = ((value as u16) << 8) | (self.tmr1 & 0x00ff), // T1H
-            0x013 => self.dan = value,               // DAN
-            0x014 => self.dseg = value,              // DSEG
-            0x015..=0x017 => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x018 => self.rcsta = value,             // RCSTA
-            0x019 => self.tx_reg = value,            // TXREG
-            0x01A => self.rc_reg = value,            // RCREG
-            0x01B => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x01C => self.ptr1_l = value,            // PTR1L
-            0x01D => self.ptr1_h = value,            // PTR1H
-            0x01E => self.ptr2_l = value,            // PTR2L
-            0x01F => self.ptr2_h = value,            // PTR2H
-
-            0x085..=0x08A => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x08D => panic!("UNASIGNED ADDRESS {address}"),         // UNASIGNED
-            0x090..=0x09F => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x105..=0x10A => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x10C..=0x10D => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x185..=0x18A => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x18C..=0x18D => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
+            0x00E | 0x08E | 0x10E | 0x18E => self.write_pointer(0, value), // INDF1
+            0x00F | 0x08F | 0x10F | 0x18F => self.write_pointer(1, value), // INDF2
+            0x01C..=0x01F => {
+                let ptr = &mut self.ptr[(address as usize - 0x01C) / 2];
+                *ptr = if address & 1 == 0 {
+                    (*ptr & 0xFF00) | value as u16
+                } else {
+                    ((value as u16) << 8) | (*ptr & 0x00FF)
+                };
+            } // PTR1L, PTR1H, PTR2L, PTR2H
 
             // NORMAL RAM
             0x020..=0x06f | 0x0A0..=0x0EF | 0x120..=0x16f | 0x1A0..=0x1EF => {
@@ -726,75 +1043,63 @@ impl P16Core {
                 self.file.write(0x070 | (address & 0xf), value);
             }
 
-            0x200..=u16::MAX => unreachable!("Write outside of the RAM"),
+            // Beyond the four banks
+            0x200..=u16::MAX => self.unimplemented_access(address, true),
+
+            // Peripherals, then unimplemented addresses
+            _ => {
+                if !self.bus_write(address, value) {
+                    self.unimplemented_access(address, true);
+                }
+            }
         }
         #[cfg(feature = "flame")]
         flame::end("write");
     }
 
+    /// Reads a register. Addresses up to 0x7F are relative to the bank
+    /// selected by STATUS RP1:RP0, as in an instruction; 0x80–0x1FF address
+    /// the register file directly. Anything higher is unimplemented and, like
+    /// any other unimplemented address read from here, reads as zero.
     #[cfg_attr(feature = "trace", tracing::instrument(skip(self)))]
-    pub fn read(&self, address: u16) -> u8 {
+    pub fn read(&mut self, address: u16) -> u8 {
+        self.read_data(self.bank_address(address))
+    }
+
+    fn read_data(&mut self, address: u16) -> u8 {
         #[cfg(feature = "flame")]
         flame::start("read");
-        #[cfg(feature = "flame")]
-        flame::start("address");
-        let address = if address > 0xff {
-            address
-        } else {
-            (((self.status.rp1 as u16) << 1 | (self.status.rp0 as u16)) << 8) | (address as u16)
-        };
-        #[cfg(feature = "flame")]
-        flame::end("address");
 
         let value = match address {
             0x000 | 0x080 | 0x100 | 0x180 => {
                 #[cfg(feature = "flame")]
                 flame::start_guard("INDF");
-                if self.fsr == 0 {
+                // Reading INDF through INDF yields 0.
+                let target = self.indirect_address();
+                if target & 0x7F == 0 {
                     0
                 } else {
-                    self.read((if self.status.irp == true { 1 } else { 0 }) << 8 | self.fsr as u16)
+                    self.read_data(target)
                 }
             } // Indirect addr
-            0x001 | 0x101 => self.tmr0,           // TMR0
             0x081 | 0x181 => self.option.value(), // OPTION_REG
             0x002 | 0x082 | 0x102 | 0x182 => (self.pc & 0xff) as u8, // PCL
             0x003 | 0x083 | 0x103 | 0x183 => self.status.value(), // STATUS
             0x004 | 0x084 | 0x104 | 0x184 => self.fsr, // FSR
-            0x005 => self.port_a,                 // PORTA
-            0x006 => self.port_b,                 // PORTB
-            0x007 => self.port_c,                 // PORTC
-            0x008 => self.port_d,                 // PORTD
-            0x009 => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
             0x00A => self.pclath,                 // PCLATH
             0x00B | 0x08B | 0x10B | 0x18B => self.intcon.value(), // INTCON
             0x00C => self.pir1.value(),           // PIR1
             0x08C => self.pie1.value(),           // PIE1
-            0x00D => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x00E | 0x08E | 0x10E | 0x18E => self.indf1, // INDF1
-            0x00F | 0x08F | 0x10F | 0x18F => self.indf2, // INDF2
-            0x010 => self.t1con.value(),          // T1CON
-            0x011 => (self.tmr1 & 0x00ff) as u8,  // T1L
-            0x012 => ((self.tmr1 & 0xff00) >> 8) as u8, // T1H
-            0x013 => self.dan,                    // DAN
-            0x014 => self.dseg,                   // DSEG
-            0x015..=0x017 => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x018 => self.rcsta,                  // RCSTA
-            0x019 => self.tx_reg,                 // TXREG
-            0x01A => self.rc_reg,                 // RCREG
-            0x01B => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x01C => self.ptr1_l,                 // PTR1L
-            0x01D => self.ptr1_h,                 // PTR1H
-            0x01E => self.ptr2_l,                 // PTR2L
-            0x01F => self.ptr2_h,                 // PTR2H
-
-            0x085..=0x08A => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x08D => panic!("UNASIGNED ADDRESS {address}"),         // UNASIGNED
-            0x090..=0x09F => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x105..=0x10A => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x10C..=0x10D => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x185..=0x18A => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
-            0x18C..=0x18D => panic!("UNASIGNED ADDRESS {address}"), // UNASIGNED
+            0x00E | 0x08E | 0x10E | 0x18E => self.read_pointer(0), // INDF1
+            0x00F | 0x08F | 0x10F | 0x18F => self.read_pointer(1), // INDF2
+            0x01C..=0x01F => {
+                let ptr = self.ptr[(address as usize - 0x01C) / 2];
+                if address & 1 == 0 {
+                    ptr as u8
+                } else {
+                    (ptr >> 8) as u8
+                }
+            } // PTR1L, PTR1H, PTR2L, PTR2H
 
             // NORMAL RAM
             0x020..=0x06f | 0x0A0..=0x0EF | 0x120..=0x16f | 0x1A0..=0x1EF => {
@@ -809,15 +1114,38 @@ impl P16Core {
                 self.file.read(0x070 | (address & 0xf))
             }
 
-            0x200..=u16::MAX => unreachable!("Read outside of the RAM"),
+            // Beyond the four banks
+            0x200..=u16::MAX => {
+                self.unimplemented_access(address, false);
+                0
+            }
+
+            // Peripherals, then unimplemented addresses
+            _ => self.bus_read(address).unwrap_or_else(|| {
+                self.unimplemented_access(address, false);
+                0
+            }),
         };
         #[cfg(feature = "flame")]
         flame::end("read");
         value
     }
 
-    pub fn intterupt(&mut self) {
-        self.stack.push_front(self.pc);
+    /// Enters the interrupt vector: clears GIE, pushes the return address and
+    /// jumps to 0x0004. Like a CALL this takes two instruction cycles, during
+    /// which the timers keep running; together with the instruction in flight
+    /// that gives the datasheet latency of three to four cycles.
+    pub fn interrupt(&mut self) {
+        self.intcon.gie = false;
+        self.stack.push(self.pc, self.pc);
         self.pc = 0x4;
+        for _ in 0..INTERRUPT_ENTRY_CYCLES {
+            self.tick();
+        }
     }
 }
+
+/// Whether `address` is INDF, INDF1 or INDF2 in any bank.
+fn is_indirect_port(address: u16) -> bool {
+    matches!(address & 0x7F, 0x00 | 0x0E | 0x0F)
+}
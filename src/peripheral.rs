@@ -0,0 +1,235 @@
+//! Extension point for the special-function registers.
+//!
+//! Everything the core does not need to execute instructions (timers, ports,
+//! the UART and the display) is a [`Peripheral`] attached to the core's
+//! [`Bus`]. Accesses to an address the core itself does not implement are
+//! dispatched to the peripheral that decodes it, and every peripheral is
+//! ticked once per instruction cycle.
+
+use std::{any::Any, fmt};
+
+use crate::{
+    display::Display, p16core::ResetKind, ports::Ports, regs, tmr0::Timer0, tmr1::Timer1,
+    uart::Uart,
+};
+
+/// Interrupt flags a peripheral can drive. Each maps to one flag bit in
+/// INTCON or PIR1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Irq {
+    /// INTCON T0IF.
+    Tmr0,
+    /// INTCON INTF.
+    Int,
+    /// INTCON RBIF.
+    PortChange,
+    /// PIR1 bits, from TMR1IF (bit 0) to PSPIF (bit 7).
+    Tmr1,
+    Tmr2,
+    Ccp1,
+    Ssp,
+    Tx,
+    Rc,
+    Ad,
+    Psp,
+}
+
+impl Irq {
+    /// Bit position of the flag in PIR1, for peripheral interrupts.
+    pub fn pir1_bit(self) -> Option<u8> {
+        match self {
+            Irq::Tmr0 | Irq::Int | Irq::PortChange => None,
+            Irq::Tmr1 => Some(0),
+            Irq::Tmr2 => Some(1),
+            Irq::Ccp1 => Some(2),
+            Irq::Ssp => Some(3),
+            Irq::Tx => Some(4),
+            Irq::Rc => Some(5),
+            Irq::Ad => Some(6),
+            Irq::Psp => Some(7),
+        }
+    }
+
+    fn mask(self) -> u16 {
+        match self {
+            Irq::Tmr0 => 1 << 8,
+            Irq::Int => 1 << 9,
+            Irq::PortChange => 1 << 10,
+            _ => 1 << self.pir1_bit().unwrap_or_default(),
+        }
+    }
+}
+
+/// Interrupt flag changes requested by peripherals, applied by the core
+/// after each call into the bus.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IrqLines {
+    raised: u16,
+    lowered: u16,
+}
+
+impl IrqLines {
+    pub fn raised(&self, irq: Irq) -> bool {
+        self.raised & irq.mask() != 0
+    }
+
+    pub fn lowered(&self, irq: Irq) -> bool {
+        self.lowered & irq.mask() != 0
+    }
+}
+
+/// Core state a peripheral may look at while handling an access or a tick.
+pub struct Context<'a> {
+    pub option: &'a regs::Option,
+    /// Oscillator frequency; one instruction cycle takes four clocks.
+    pub clock_hz: u32,
+    lines: &'a mut IrqLines,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(option: &'a regs::Option, clock_hz: u32, lines: &'a mut IrqLines) -> Self {
+        Self {
+            option,
+            clock_hz,
+            lines,
+        }
+    }
+
+    /// Sets an interrupt flag. It stays set until firmware clears it.
+    pub fn raise(&mut self, irq: Irq) {
+        self.lines.raised |= irq.mask();
+        self.lines.lowered &= !irq.mask();
+    }
+
+    /// Clears an interrupt flag, for flags the hardware clears itself, such
+    /// as RCIF once RCREG has been read.
+    pub fn lower(&mut self, irq: Irq) {
+        self.lines.lowered |= irq.mask();
+        self.lines.raised &= !irq.mask();
+    }
+}
+
+/// A device mapped into the register file.
+///
+/// Addresses are full 9-bit data memory addresses with the bank already
+/// applied, so a register mirrored in several banks must decode each copy.
+pub trait Peripheral: PeripheralBase + fmt::Debug {
+    /// Whether this peripheral implements the register at `address`.
+    fn decodes(&self, address: u16) -> bool;
+
+    fn read(&mut self, address: u16, cx: &mut Context) -> u8;
+
+    fn write(&mut self, address: u16, value: u8, cx: &mut Context);
+
+    /// Advances the peripheral by one instruction cycle while the core runs.
+    fn tick(&mut self, _cx: &mut Context) {}
+
+    /// Loads the register values the datasheet lists for `kind`.
+    fn reset(&mut self, _kind: ResetKind) {}
+}
+
+/// Cloning and downcasting support, implemented for every `Clone` peripheral.
+#[doc(hidden)]
+pub trait PeripheralBase {
+    fn clone_box(&self) -> Box<dyn Peripheral>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Peripheral + Clone + 'static> PeripheralBase for T {
+    fn clone_box(&self) -> Box<dyn Peripheral> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Clone for Box<dyn Peripheral> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// The peripherals attached to a core.
+///
+/// When several peripherals decode the same address, the one attached last
+/// wins, so a custom model can replace a built-in register.
+#[derive(Debug, Clone, Default)]
+pub struct Bus {
+    peripherals: Vec<Box<dyn Peripheral>>,
+}
+
+impl Bus {
+    /// The built-in p16core peripherals: TMR0, TMR1, the ports, the UART and
+    /// the display.
+    pub fn p16core() -> Self {
+        let mut bus = Self::default();
+        bus.attach(Timer0::default());
+        bus.attach(Timer1::default());
+        bus.attach(Ports::default());
+        bus.attach(Uart::default());
+        bus.attach(Display::default());
+        bus
+    }
+
+    pub fn attach(&mut self, peripheral: impl Peripheral + 'static) {
+        self.peripherals.push(Box::new(peripheral));
+    }
+
+    /// The most recently attached peripheral of type `T`.
+    pub fn get<T: Peripheral + 'static>(&self) -> Option<&T> {
+        self.peripherals
+            .iter()
+            .rev()
+            .find_map(|peripheral| peripheral.as_any().downcast_ref())
+    }
+
+    pub fn get_mut<T: Peripheral + 'static>(&mut self) -> Option<&mut T> {
+        self.peripherals
+            .iter_mut()
+            .rev()
+            .find_map(|peripheral| peripheral.as_any_mut().downcast_mut())
+    }
+
+    /// Whether any peripheral decodes `address`.
+    pub fn decodes(&self, address: u16) -> bool {
+        self.peripherals.iter().any(|p| p.decodes(address))
+    }
+
+    /// Reads `address`, or returns `None` when no peripheral decodes it.
+    pub fn read(&mut self, address: u16, cx: &mut Context) -> Option<u8> {
+        self.find(address).map(|p| p.read(address, cx))
+    }
+
+    /// Writes `address`, returning `false` when no peripheral decodes it.
+    pub fn write(&mut self, address: u16, value: u8, cx: &mut Context) -> bool {
+        self.find(address)
+            .map(|p| p.write(address, value, cx))
+            .is_some()
+    }
+
+    pub fn tick(&mut self, cx: &mut Context) {
+        for peripheral in &mut self.peripherals {
+            peripheral.tick(cx);
+        }
+    }
+
+    pub fn reset(&mut self, kind: ResetKind) {
+        for peripheral in &mut self.peripherals {
+            peripheral.reset(kind);
+        }
+    }
+
+    fn find(&mut self, address: u16) -> Option<&mut Box<dyn Peripheral>> {
+        self.peripherals
+            .iter_mut()
+            .rev()
+            .find(|p| p.decodes(address))
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{
+    p16core::ResetKind,
+    peripheral::{Context, Irq, Peripheral},
+    regs,
+};
+
+/// One of the four I/O ports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Port {
+    A,
+    B,
+    C,
+    D,
+}
+
+impl Port {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// PORTA to PORTD at 0x05–0x08 and their direction registers TRISA to TRISD
+/// at 0x85–0x88.
+///
+/// Each pin is an output while its TRIS bit is clear, showing its latch bit,
+/// and an input while it is set, showing the level driven from outside.
+/// PORTB inputs left undriven are pulled high when OPTION_REG RBPU is clear;
+/// other undriven inputs read as 0. Reading a port returns the pin levels,
+/// not the latches, so `BSF`/`BCF` on a port copy input levels into the
+/// latches like the hardware's read-modify-write does. RB0 doubles as the
+/// INT pin and a level change on the RB7:RB4 inputs sets RBIF.
+#[derive(Debug, Clone)]
+pub struct Ports {
+    /// Output latches of PORTA..PORTD.
+    pub latches: [u8; 4],
+    /// TRISA..TRISD; a set bit makes the pin an input.
+    pub tris: [u8; 4],
+    /// Pins driven from outside, per port.
+    driven: [u8; 4],
+    /// Levels of the driven pins.
+    inputs: [u8; 4],
+    /// PORTB pin levels when interrupt sources were last checked.
+    port_b: u8,
+}
+
+impl Default for Ports {
+    fn default() -> Self {
+        Self {
+            latches: [0; 4],
+            tris: [0xFF; 4],
+            driven: [0; 4],
+            inputs: [0; 4],
+            port_b: 0,
+        }
+    }
+}
+
+/// Interrupt conditions found by [`Ports::sense`].
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Sensed {
+    /// An RB0/INT edge in the direction selected by INTEDG.
+    pub int_edge: bool,
+    /// A level change on an RB7:RB4 input.
+    pub port_change: bool,
+}
+
+impl Ports {
+    /// Drives `pin` of `port` from outside, or releases it with `None`.
+    /// The level only shows while the pin is an input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pin` is not below 8.
+    pub fn drive(&mut self, port: Port, pin: u8, level: Option<bool>) {
+        assert!(pin < 8, "pin {pin} out of range");
+        let (i, mask) = (port.index(), 1 << pin);
+        match level {
+            Some(level) => {
+                self.driven[i] |= mask;
+                self.inputs[i] = if level {
+                    self.inputs[i] | mask
+                } else {
+                    self.inputs[i] & !mask
+                };
+            }
+            None => self.driven[i] &= !mask,
+        }
+    }
+
+    /// The level `pin` of `port` is driven to by the core, or `None` while
+    /// it is an input.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pin` is not below 8.
+    pub fn output(&self, port: Port, pin: u8) -> Option<bool> {
+        assert!(pin < 8, "pin {pin} out of range");
+        let (i, mask) = (port.index(), 1 << pin);
+        (self.tris[i] & mask == 0).then_some(self.latches[i] & mask != 0)
+    }
+
+    /// The pin levels of `port`. `option` decides whether the PORTB pull-ups
+    /// are on.
+    pub fn levels(&self, port: Port, option: &regs::Option) -> u8 {
+        let i = port.index();
+        let mut inputs = self.inputs[i] & self.driven[i];
+        if port == Port::B && !option.rbpu {
+            inputs |= !self.driven[i];
+        }
+        (self.latches[i] & !self.tris[i]) | (inputs & self.tris[i])
+    }
+
+    /// Compares the PORTB pins with their levels at the last check.
+    pub(crate) fn sense(&mut self, option: &regs::Option) -> Sensed {
+        let levels = self.levels(Port::B, option);
+        let changed = levels ^ self.port_b;
+        self.port_b = levels;
+        Sensed {
+            int_edge: changed & 0x01 != 0 && (levels & 0x01 != 0) == option.intedg,
+            port_change: changed & self.tris[Port::B.index()] & 0xF0 != 0,
+        }
+    }
+
+    fn register(address: u16) -> (bool, usize) {
+        (address & 0x080 != 0, (address as usize & 0x7F) - 0x05)
+    }
+}
+
+impl Peripheral for Ports {
+    fn decodes(&self, address: u16) -> bool {
+        (0x005..=0x008).contains(&address) || (0x085..=0x088).contains(&address)
+    }
+
+    fn read(&mut self, address: u16, cx: &mut Context) -> u8 {
+        match Self::register(address) {
+            (true, i) => self.tris[i],
+            (false, i) => {
+                let port = [Port::A, Port::B, Port::C, Port::D][i];
+                self.levels(port, cx.option)
+            }
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8, _cx: &mut Context) {
+        match Self::register(address) {
+            (true, i) => self.tris[i] = value,
+            (false, i) => self.latches[i] = value,
+        }
+    }
+
+    fn tick(&mut self, cx: &mut Context) {
+        let sensed = self.sense(cx.option);
+        if sensed.int_edge {
+            cx.raise(Irq::Int);
+        }
+        if sensed.port_change {
+            cx.raise(Irq::PortChange);
+        }
+    }
+
+    /// Every reset turns all pins into inputs. Output latches survive every
+    /// reset but power-on and brown-out. Input levels are driven from outside
+    /// and never reset.
+    fn reset(&mut self, kind: ResetKind) {
+        self.tris = [0xFF; 4];
+        if matches!(kind, ResetKind::PowerOn | ResetKind::BrownOut) {
+            self.latches = [0; 4];
+        }
+    }
+}
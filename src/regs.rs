@@ -47,14 +47,14 @@ impl Status {
     }
 
     pub fn set(&mut self, value: u8) {
-        self.irp = if (value >> 7) & 1 == 1 { true } else { false };
-        self.rp1 = if (value >> 6) & 1 == 1 { true } else { false };
-        self.rp0 = if (value >> 5) & 1 == 1 { true } else { false };
-        self.to = if (value >> 4) & 1 == 1 { true } else { false };
-        self.pd = if (value >> 3) & 1 == 1 { true } else { false };
-        self.z = if (value >> 2) & 1 == 1 { true } else { false };
-        self.dc = if (value >> 1) & 1 == 1 { true } else { false };
-        self.c = if (value >> 0) & 1 == 1 { true } else { false };
+        self.irp = (value >> 7) & 1 == 1;
+        self.rp1 = (value >> 6) & 1 == 1;
+        self.rp0 = (value >> 5) & 1 == 1;
+        self.to = (value >> 4) & 1 == 1;
+        self.pd = (value >> 3) & 1 == 1;
+        self.z = (value >> 2) & 1 == 1;
+        self.dc = (value >> 1) & 1 == 1;
+        self.c = value & 1 == 1;
     }
 }
 
@@ -113,14 +113,14 @@ impl Option {
     }
 
     pub fn set(&mut self, value: u8) {
-        self.rbpu = if (value >> 7) & 1 == 1 { true } else { false };
-        self.intedg = if (value >> 6) & 1 == 1 { true } else { false };
-        self.t0cs = if (value >> 5) & 1 == 1 { true } else { false };
-        self.t0se = if (value >> 4) & 1 == 1 { true } else { false };
-        self.psa = if (value >> 3) & 1 == 1 { true } else { false };
-        self.ps2 = if (value >> 2) & 1 == 1 { true } else { false };
-        self.ps1 = if (value >> 1) & 1 == 1 { true } else { false };
-        self.ps0 = if (value >> 0) & 1 == 1 { true } else { false };
+        self.rbpu = (value >> 7) & 1 == 1;
+        self.intedg = (value >> 6) & 1 == 1;
+        self.t0cs = (value >> 5) & 1 == 1;
+        self.t0se = (value >> 4) & 1 == 1;
+        self.psa = (value >> 3) & 1 == 1;
+        self.ps2 = (value >> 2) & 1 == 1;
+        self.ps1 = (value >> 1) & 1 == 1;
+        self.ps0 = value & 1 == 1;
     }
 }
 
@@ -179,14 +179,14 @@ impl Intcon {
     }
 
     pub fn set(&mut self, value: u8) {
-        self.gie = if (value >> 7) & 1 == 1 { true } else { false };
-        self.peie = if (value >> 6) & 1 == 1 { true } else { false };
-        self.tmr0ie = if (value >> 5) & 1 == 1 { true } else { false };
-        self.inte = if (value >> 4) & 1 == 1 { true } else { false };
-        self.rbie = if (value >> 3) & 1 == 1 { true } else { false };
-        self.tmr0if = if (value >> 2) & 1 == 1 { true } else { false };
-        self.intf = if (value >> 1) & 1 == 1 { true } else { false };
-        self.rbif = if (value >> 0) & 1 == 1 { true } else { false };
+        self.gie = (value >> 7) & 1 == 1;
+        self.peie = (value >> 6) & 1 == 1;
+        self.tmr0ie = (value >> 5) & 1 == 1;
+        self.inte = (value >> 4) & 1 == 1;
+        self.rbie = (value >> 3) & 1 == 1;
+        self.tmr0if = (value >> 2) & 1 == 1;
+        self.intf = (value >> 1) & 1 == 1;
+        self.rbif = value & 1 == 1;
     }
 }
 
@@ -245,14 +245,14 @@ impl PIE1 {
     }
 
     pub fn set(&mut self, value: u8) {
-        self.pspie = if (value >> 7) & 1 == 1 { true } else { false };
-        self.adie = if (value >> 6) & 1 == 1 { true } else { false };
-        self.rcie = if (value >> 5) & 1 == 1 { true } else { false };
-        self.txie = if (value >> 4) & 1 == 1 { true } else { false };
-        self.sspie = if (value >> 3) & 1 == 1 { true } else { false };
-        self.ccp1ie = if (value >> 2) & 1 == 1 { true } else { false };
-        self.tmr2ie = if (value >> 1) & 1 == 1 { true } else { false };
-        self.tmr1ie = if (value >> 0) & 1 == 1 { true } else { false };
+        self.pspie = (value >> 7) & 1 == 1;
+        self.adie = (value >> 6) & 1 == 1;
+        self.rcie = (value >> 5) & 1 == 1;
+        self.txie = (value >> 4) & 1 == 1;
+        self.sspie = (value >> 3) & 1 == 1;
+        self.ccp1ie = (value >> 2) & 1 == 1;
+        self.tmr2ie = (value >> 1) & 1 == 1;
+        self.tmr1ie = value & 1 == 1;
     }
 }
 
@@ -311,14 +311,14 @@ impl PIR1 {
     }
 
     pub fn set(&mut self, value: u8) {
-        self.pspif = if (value >> 7) & 1 == 1 { true } else { false };
-        self.adif = if (value >> 6) & 1 == 1 { true } else { false };
-        self.rcif = if (value >> 5) & 1 == 1 { true } else { false };
-        self.txif = if (value >> 4) & 1 == 1 { true } else { false };
-        self.sspif = if (value >> 3) & 1 == 1 { true } else { false };
-        self.ccp1if = if (value >> 2) & 1 == 1 { true } else { false };
-        self.tmr2if = if (value >> 1) & 1 == 1 { true } else { false };
-        self.tmr1if = if (value >> 0) & 1 == 1 { true } else { false };
+        self.pspif = (value >> 7) & 1 == 1;
+        self.adif = (value >> 6) & 1 == 1;
+        self.rcif = (value >> 5) & 1 == 1;
+        self.txif = (value >> 4) & 1 == 1;
+        self.sspif = (value >> 3) & 1 == 1;
+        self.ccp1if = (value >> 2) & 1 == 1;
+        self.tmr2if = (value >> 1) & 1 == 1;
+        self.tmr1if = value & 1 == 1;
     }
 }
 
@@ -369,12 +369,12 @@ impl T1CON {
     }
 
     pub fn set(&mut self, value: u8) {
-        self.t1ckps1 = if (value >> 5) & 1 == 1 { true } else { false };
-        self.t1ckps0 = if (value >> 4) & 1 == 1 { true } else { false };
-        self.t1oscen = if (value >> 3) & 1 == 1 { true } else { false };
-        self.t1sync = if (value >> 2) & 1 == 1 { true } else { false };
-        self.tmr1cs = if (value >> 1) & 1 == 1 { true } else { false };
-        self.tmr1on = if (value >> 0) & 1 == 1 { true } else { false };
+        self.t1ckps1 = (value >> 5) & 1 == 1;
+        self.t1ckps0 = (value >> 4) & 1 == 1;
+        self.t1oscen = (value >> 3) & 1 == 1;
+        self.t1sync = (value >> 2) & 1 == 1;
+        self.tmr1cs = (value >> 1) & 1 == 1;
+        self.tmr1on = value & 1 == 1;
     }
 }
 
@@ -383,3 +383,136 @@ impl Default for T1CON {
         Self::new(0x00)
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Oscillator {
+    Lp,
+    Xt,
+    Hs,
+    Rc,
+}
+
+/// Configuration word at program address 0x2007.
+///
+/// Fields hold the raw bits, so the active-low ones (`cp`, `debug`, `cpd`,
+/// `pwrte`) read `true` when the feature is *disabled*; prefer the helper
+/// methods.
+///
+/// WDTE enables the watchdog and CP/CPD protect what
+/// [`P16Core::read_back`](crate::P16Core::read_back) returns. The other
+/// fields are informational only: FOSC selects an oscillator type, not a
+/// frequency, so the clock comes from `P16Core::set_clock_hz`, and brown-out,
+/// power-up timer, low-voltage programming, debug and write-protect settings
+/// are not modelled.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub cp: bool,
+    pub debug: bool,
+    pub wrt1: bool,
+    pub wrt0: bool,
+    pub cpd: bool,
+    pub lvp: bool,
+    pub boren: bool,
+    pub pwrte: bool,
+    pub wdte: bool,
+    pub fosc1: bool,
+    pub fosc0: bool,
+    /// The word as loaded, for the bits no field covers.
+    raw: u16,
+}
+
+/// Config word bits without a field of their own; [`Config::value`] returns
+/// them as loaded.
+const CONFIG_UNIMPLEMENTED: u16 = 1 << 12 | 1 << 5 | 1 << 4;
+
+impl Config {
+    pub fn value(&self) -> u16 {
+        let mut value = 0;
+        value |= if self.cp { 1 } else { 0 };
+        value <<= 2;
+        value |= if self.debug { 1 } else { 0 };
+        value <<= 1;
+        value |= if self.wrt1 { 1 } else { 0 };
+        value <<= 1;
+        value |= if self.wrt0 { 1 } else { 0 };
+        value <<= 1;
+        value |= if self.cpd { 1 } else { 0 };
+        value <<= 1;
+        value |= if self.lvp { 1 } else { 0 };
+        value <<= 1;
+        value |= if self.boren { 1 } else { 0 };
+        value <<= 3;
+        value |= if self.pwrte { 1 } else { 0 };
+        value <<= 1;
+        value |= if self.wdte { 1 } else { 0 };
+        value <<= 1;
+        value |= if self.fosc1 { 1 } else { 0 };
+        value <<= 1;
+        value |= if self.fosc0 { 1 } else { 0 };
+        value | (self.raw & CONFIG_UNIMPLEMENTED)
+    }
+
+    pub fn new(value: u16) -> Self {
+        let mut s = Self {
+            cp: false,
+            debug: false,
+            wrt1: false,
+            wrt0: false,
+            cpd: false,
+            lvp: false,
+            boren: false,
+            pwrte: false,
+            wdte: false,
+            fosc1: false,
+            fosc0: false,
+            raw: 0,
+        };
+        s.set(value);
+        s
+    }
+
+    pub fn set(&mut self, value: u16) {
+        self.raw = value & 0x3FFF;
+        self.cp = (value >> 13) & 1 == 1;
+        self.debug = (value >> 11) & 1 == 1;
+        self.wrt1 = (value >> 10) & 1 == 1;
+        self.wrt0 = (value >> 9) & 1 == 1;
+        self.cpd = (value >> 8) & 1 == 1;
+        self.lvp = (value >> 7) & 1 == 1;
+        self.boren = (value >> 6) & 1 == 1;
+        self.pwrte = (value >> 3) & 1 == 1;
+        self.wdte = (value >> 2) & 1 == 1;
+        self.fosc1 = (value >> 1) & 1 == 1;
+        self.fosc0 = value & 1 == 1;
+    }
+
+    pub fn oscillator(&self) -> Oscillator {
+        match (self.fosc1, self.fosc0) {
+            (false, false) => Oscillator::Lp,
+            (false, true) => Oscillator::Xt,
+            (true, false) => Oscillator::Hs,
+            (true, true) => Oscillator::Rc,
+        }
+    }
+
+    pub fn watchdog_enabled(&self) -> bool {
+        self.wdte
+    }
+
+    /// Program memory code protection.
+    pub fn code_protected(&self) -> bool {
+        !self.cp
+    }
+
+    /// Data EEPROM code protection.
+    pub fn data_protected(&self) -> bool {
+        !self.cpd
+    }
+}
+
+impl Default for Config {
+    /// Erased configuration word.
+    fn default() -> Self {
+        Self::new(0x3FFF)
+    }
+}
@@ -0,0 +1,277 @@
+//! Host-side backends for the UART.
+//!
+//! A [`Connection`] pumps bytes between a [`UartHost`] and the process's
+//! stdin/stdout, a pseudo-terminal or a TCP socket on background threads, so
+//! firmware can be driven interactively like a real board.
+
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    net::TcpListener,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::uart::UartHost;
+
+/// How often transmitted bytes are forwarded to the backend.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Where the UART is connected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Backend {
+    /// The process's stdin and stdout. The terminal stays in line mode, so
+    /// input reaches the firmware a line at a time.
+    Stdio,
+    /// A new Unix pseudo-terminal for `screen`, `minicom` and the like.
+    Pty,
+    /// A TCP listener. One client is served at a time; output is held back
+    /// while none is connected.
+    Tcp(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBackendError(String);
+
+impl fmt::Display for ParseBackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown UART backend `{}` (expected stdio, pty, tcp:PORT or tcp:HOST:PORT)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseBackendError {}
+
+impl FromStr for Backend {
+    type Err = ParseBackendError;
+
+    /// Parses `stdio`, `pty`, `tcp:PORT` (on localhost) or `tcp:HOST:PORT`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stdio" => Ok(Backend::Stdio),
+            "pty" => Ok(Backend::Pty),
+            _ => match s.strip_prefix("tcp:") {
+                Some(port) if port.parse::<u16>().is_ok() => {
+                    Ok(Backend::Tcp(format!("127.0.0.1:{port}")))
+                }
+                Some(address) if !address.is_empty() => Ok(Backend::Tcp(address.to_string())),
+                _ => Err(ParseBackendError(s.to_string())),
+            },
+        }
+    }
+}
+
+type Output = Arc<Mutex<Option<Box<dyn Write + Send>>>>;
+
+/// A UART attached to a [`Backend`]. The pumping threads run for the rest of
+/// the process.
+pub struct Connection {
+    host: UartHost,
+    output: Output,
+    description: String,
+    /// Our handle on the pseudo-terminal's slave side, which keeps the master
+    /// readable while no terminal program is attached.
+    #[cfg(unix)]
+    _pty_slave: Option<std::fs::File>,
+}
+
+impl fmt::Debug for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Connection")
+            .field("description", &self.description)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Connection {
+    /// Connects `host` to `backend` and starts forwarding bytes both ways.
+    pub fn open(backend: &Backend, host: UartHost) -> io::Result<Self> {
+        let output: Output = Arc::new(Mutex::new(None));
+        let mut connection = Self {
+            host: host.clone(),
+            output: output.clone(),
+            description: String::new(),
+            #[cfg(unix)]
+            _pty_slave: None,
+        };
+
+        match backend {
+            Backend::Stdio => {
+                *lock(&output) = Some(Box::new(io::stdout()));
+                spawn_reader(io::stdin(), host.clone());
+                connection.description = "stdin/stdout".to_string();
+            }
+            Backend::Pty => {
+                let pty = pty::open()?;
+                *lock(&output) = Some(Box::new(pty.master.try_clone()?));
+                spawn_reader(pty.master, host.clone());
+                connection.description = pty.path;
+                #[cfg(unix)]
+                {
+                    connection._pty_slave = Some(pty.slave);
+                }
+            }
+            Backend::Tcp(address) => {
+                let listener = TcpListener::bind(address)?;
+                connection.description = format!("tcp {}", listener.local_addr()?);
+                let output = output.clone();
+                let host = host.clone();
+                thread::spawn(move || {
+                    for stream in listener.incoming() {
+                        let Ok(stream) = stream else { continue };
+                        let Ok(writer) = stream.try_clone() else {
+                            continue;
+                        };
+                        *lock(&output) = Some(Box::new(writer));
+                        pump(stream, &host);
+                        *lock(&output) = None;
+                    }
+                });
+            }
+        }
+
+        thread::spawn(move || {
+            loop {
+                thread::sleep(POLL_INTERVAL);
+                forward(&host, &output);
+            }
+        });
+        Ok(connection)
+    }
+
+    /// Where the UART is connected, such as `/dev/pts/3`.
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    /// Forwards output the pumping thread has not picked up yet.
+    pub fn flush(&self) {
+        forward(&self.host, &self.output);
+    }
+}
+
+fn lock(output: &Output) -> std::sync::MutexGuard<'_, Option<Box<dyn Write + Send>>> {
+    output.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Writes transmitted bytes to the backend. They stay queued in `host` while
+/// nothing is connected; a write error disconnects the output.
+fn forward(host: &UartHost, output: &Output) {
+    let mut output = lock(output);
+    let Some(writer) = output.as_mut() else {
+        return;
+    };
+    let bytes = host.read();
+    if bytes.is_empty() {
+        return;
+    }
+    if writer
+        .write_all(&bytes)
+        .and_then(|()| writer.flush())
+        .is_err()
+    {
+        *output = None;
+    }
+}
+
+fn spawn_reader(reader: impl Read + Send + 'static, host: UartHost) {
+    thread::spawn(move || pump(reader, &host));
+}
+
+/// Queues everything read from `reader` for the firmware, until end of
+/// file or an error.
+fn pump(mut reader: impl Read, host: &UartHost) {
+    let mut buffer = [0; 256];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => return,
+            Ok(n) => host.write(&buffer[..n]),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(_) => return,
+        }
+    }
+}
+
+#[cfg(unix)]
+mod pty {
+    use std::{
+        ffi::CStr,
+        fs::{File, OpenOptions},
+        io,
+        os::{
+            fd::{AsRawFd, FromRawFd},
+            unix::fs::OpenOptionsExt,
+        },
+    };
+
+    pub struct Pty {
+        pub master: File,
+        pub slave: File,
+        pub path: String,
+    }
+
+    fn check(ret: libc::c_int) -> io::Result<()> {
+        if ret < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Opens a new pseudo-terminal in raw mode, so bytes pass through
+    /// without echo or line editing.
+    pub fn open() -> io::Result<Pty> {
+        // SAFETY: plain libc calls on a descriptor we own; `ptsname` is read
+        // immediately, before any other pty is opened.
+        unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+            check(fd)?;
+            let master = File::from_raw_fd(fd);
+            check(libc::grantpt(fd))?;
+            check(libc::unlockpt(fd))?;
+            let name = libc::ptsname(fd);
+            if name.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let path = CStr::from_ptr(name).to_string_lossy().into_owned();
+
+            let slave = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .custom_flags(libc::O_NOCTTY)
+                .open(&path)?;
+            let mut termios = std::mem::zeroed::<libc::termios>();
+            check(libc::tcgetattr(slave.as_raw_fd(), &mut termios))?;
+            libc::cfmakeraw(&mut termios);
+            check(libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios))?;
+
+            Ok(Pty {
+                master,
+                slave,
+                path,
+            })
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod pty {
+    use std::{fs::File, io};
+
+    pub struct Pty {
+        pub master: File,
+        pub path: String,
+    }
+
+    pub fn open() -> io::Result<Pty> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "pseudo-terminals need a Unix host",
+        ))
+    }
+}
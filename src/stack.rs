@@ -0,0 +1,127 @@
+use std::fmt;
+
+/// Number of levels in the hardware return stack.
+pub const STACK_DEPTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StackFault {
+    /// A ninth return address was pushed, overwriting the oldest one.
+    Overflow,
+    /// A return was executed with no matching call.
+    Underflow,
+}
+
+/// A stack fault recorded while diagnostics are enabled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackEvent {
+    pub fault: StackFault,
+    /// Address of the instruction, or interrupted instruction, that caused it.
+    pub pc: u16,
+    /// Addresses of the calls still on the stack, oldest first, before the
+    /// faulting access.
+    pub calls: Vec<u16>,
+}
+
+impl fmt::Display for StackEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fault = match self.fault {
+            StackFault::Overflow => "overflow",
+            StackFault::Underflow => "underflow",
+        };
+        write!(f, "stack {fault} at 0x{:04X}", self.pc)?;
+        if !self.calls.is_empty() {
+            let calls: Vec<String> = self
+                .calls
+                .iter()
+                .map(|call| format!("0x{call:04X}"))
+                .collect();
+            write!(f, " (calls: {})", calls.join(" > "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The 8-level return stack.
+///
+/// Like the hardware it is a circular buffer: a ninth push overwrites the
+/// oldest entry and popping an empty stack returns whatever the slot below
+/// holds. Neither is an error unless diagnostics are enabled, in which case
+/// each fault is recorded as a [`StackEvent`].
+#[derive(Debug, Clone, Default)]
+pub struct Stack {
+    entries: [u16; STACK_DEPTH],
+    pointer: usize,
+    diagnostics: bool,
+    /// Call sites matching the live entries, kept only with diagnostics on.
+    calls: Vec<u16>,
+    events: Vec<StackEvent>,
+}
+
+impl Stack {
+    /// Pushes a return address. `from` is the address of the call or
+    /// interrupted instruction, used in diagnostics.
+    pub fn push(&mut self, address: u16, from: u16) {
+        self.entries[self.pointer] = address;
+        self.pointer = (self.pointer + 1) % STACK_DEPTH;
+
+        if self.diagnostics {
+            if self.calls.len() == STACK_DEPTH {
+                self.record(StackFault::Overflow, from);
+                self.calls.remove(0);
+            }
+            self.calls.push(from);
+        }
+    }
+
+    /// Pops a return address. `from` is the address of the return
+    /// instruction, used in diagnostics.
+    pub fn pop(&mut self, from: u16) -> u16 {
+        self.pointer = (self.pointer + STACK_DEPTH - 1) % STACK_DEPTH;
+
+        if self.diagnostics && self.calls.pop().is_none() {
+            self.record(StackFault::Underflow, from);
+        }
+        self.entries[self.pointer]
+    }
+
+    /// Moves the stack pointer back to the bottom, as a reset does. Entries
+    /// and recorded events are kept.
+    pub fn reset(&mut self) {
+        self.pointer = 0;
+        self.calls.clear();
+    }
+
+    pub fn diagnostics(&self) -> bool {
+        self.diagnostics
+    }
+
+    /// Turns overflow and underflow reporting on or off. Call history is
+    /// tracked from this point on.
+    pub fn set_diagnostics(&mut self, enabled: bool) {
+        self.diagnostics = enabled;
+        self.calls.clear();
+    }
+
+    /// Number of return addresses pushed and not yet popped, as far as the
+    /// diagnostics can tell. Always 0 with diagnostics off.
+    pub fn depth(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn events(&self) -> &[StackEvent] {
+        &self.events
+    }
+
+    /// Returns the recorded events and clears the log.
+    pub fn take_events(&mut self) -> Vec<StackEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn record(&mut self, fault: StackFault, pc: u16) {
+        self.events.push(StackEvent {
+            fault,
+            pc,
+            calls: self.calls.clone(),
+        });
+    }
+}
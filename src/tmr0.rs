@@ -0,0 +1,58 @@
+use crate::{
+    p16core::ResetKind,
+    peripheral::{Context, Irq, Peripheral},
+};
+
+/// TMR0, the 8-bit timer at 0x01 and 0x101.
+///
+/// In timer mode (OPTION_REG T0CS clear) it counts instruction cycles through
+/// the prescaler when PSA assigns it to TMR0. Counter mode is not modelled,
+/// as nothing drives T0CKI.
+#[derive(Debug, Clone, Default)]
+pub struct Timer0 {
+    pub value: u8,
+    prescale_counter: u16,
+}
+
+impl Peripheral for Timer0 {
+    fn decodes(&self, address: u16) -> bool {
+        matches!(address, 0x001 | 0x101)
+    }
+
+    fn read(&mut self, _address: u16, _cx: &mut Context) -> u8 {
+        self.value
+    }
+
+    /// Writing TMR0 also clears the prescaler.
+    fn write(&mut self, _address: u16, value: u8, _cx: &mut Context) {
+        self.value = value;
+        self.prescale_counter = 0;
+    }
+
+    fn tick(&mut self, cx: &mut Context) {
+        if cx.option.t0cs {
+            return;
+        }
+        self.prescale_counter += 1;
+        let prescale = if cx.option.psa {
+            1
+        } else {
+            2 << (cx.option.value() & 0b00000111)
+        };
+        if self.prescale_counter >= prescale {
+            self.prescale_counter = 0;
+            let (value, overflow) = self.value.overflowing_add(1);
+            self.value = value;
+            if overflow {
+                cx.raise(Irq::Tmr0);
+            }
+        }
+    }
+
+    fn reset(&mut self, kind: ResetKind) {
+        self.prescale_counter = 0;
+        if matches!(kind, ResetKind::PowerOn | ResetKind::BrownOut) {
+            self.value = 0;
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{
+    p16core::ResetKind,
+    peripheral::{Context, Irq, Peripheral},
+    regs,
+};
+
+/// TMR1, the 16-bit timer behind T1CON (0x10), T1L (0x11) and T1H (0x12).
+///
+/// It counts instruction cycles through the T1CKPS prescaler while TMR1ON is
+/// set. The external clock input is not modelled, so TMR1CS stops it.
+#[derive(Debug, Clone, Default)]
+pub struct Timer1 {
+    pub t1con: regs::T1CON,
+    pub value: u16,
+    prescale_counter: u8,
+}
+
+impl Peripheral for Timer1 {
+    fn decodes(&self, address: u16) -> bool {
+        (0x010..=0x012).contains(&address)
+    }
+
+    fn read(&mut self, address: u16, _cx: &mut Context) -> u8 {
+        match address {
+            0x010 => self.t1con.value(),
+            0x011 => (self.value & 0x00ff) as u8,
+            _ => ((self.value & 0xff00) >> 8) as u8,
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8, _cx: &mut Context) {
+        match address {
+            0x010 => self.t1con.set(value),
+            0x011 => self.value = (self.value & 0xff00) | (value as u16),
+            _ => self.value = ((value as u16) << 8) | (self.value & 0x00ff),
+        }
+    }
+
+    fn tick(&mut self, cx: &mut Context) {
+        if !self.t1con.tmr1on || self.t1con.tmr1cs {
+            return;
+        }
+        self.prescale_counter += 1;
+        let v = self.t1con.value();
+        if self.prescale_counter >= 1 << ((v & 0b00110000) >> 4) {
+            self.prescale_counter = 0;
+            let (value, overflow) = self.value.overflowing_add(1);
+            self.value = value;
+            if overflow {
+                cx.raise(Irq::Tmr1);
+            }
+        }
+    }
+
+    /// T1CON and the count survive every reset but power-on and brown-out.
+    fn reset(&mut self, kind: ResetKind) {
+        self.prescale_counter = 0;
+        if matches!(kind, ResetKind::PowerOn | ResetKind::BrownOut) {
+            *self = Self::default();
+        }
+    }
+}
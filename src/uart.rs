@@ -0,0 +1,247 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    p16core::ResetKind,
+    peripheral::{Context, Irq, Peripheral},
+};
+
+/// Default line speed of a new [`Uart`].
+pub const DEFAULT_BAUD_RATE: u32 = 9600;
+
+const RCSTA: u16 = 0x018;
+const TXREG: u16 = 0x019;
+
+const SPEN: u8 = 1 << 7;
+const CREN: u8 = 1 << 4;
+const FERR: u8 = 1 << 2;
+const OERR: u8 = 1 << 1;
+/// RCSTA bits firmware can write; FERR, OERR and RX9D are read-only.
+const CONTROL_BITS: u8 = 0b1111_1000;
+
+/// Depth of the receive FIFO behind RCREG.
+const RX_FIFO_DEPTH: usize = 2;
+
+/// Bits per frame: start bit, eight data bits and stop bit.
+const FRAME_BITS: u64 = 10;
+
+/// Host side of the UART, shared with the [`Uart`] it came from.
+///
+/// Bytes the firmware transmits queue up here until the host reads them, and
+/// bytes the host writes are received by the firmware one frame time apart.
+/// The handle is cheap to clone and can be moved to another thread.
+#[derive(Debug, Clone, Default)]
+pub struct UartHost {
+    buffers: Arc<Mutex<HostBuffers>>,
+}
+
+#[derive(Debug, Default)]
+struct HostBuffers {
+    transmitted: VecDeque<u8>,
+    /// Bytes waiting to be received, with whether the frame is malformed.
+    to_receive: VecDeque<(u8, bool)>,
+}
+
+impl UartHost {
+    /// Queues bytes for the firmware to receive.
+    pub fn write(&self, bytes: &[u8]) {
+        let mut buffers = self.lock();
+        buffers
+            .to_receive
+            .extend(bytes.iter().map(|&byte| (byte, false)));
+    }
+
+    /// Queues a byte whose stop bit is missing, so it arrives with FERR set.
+    pub fn write_framing_error(&self, byte: u8) {
+        self.lock().to_receive.push_back((byte, true));
+    }
+
+    /// Takes every byte the firmware has transmitted so far.
+    pub fn read(&self) -> Vec<u8> {
+        self.lock().transmitted.drain(..).collect()
+    }
+
+    /// Number of bytes still waiting to be received by the firmware.
+    pub fn pending(&self) -> usize {
+        self.lock().to_receive.len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HostBuffers> {
+        self.buffers.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// The asynchronous serial port behind RCSTA (0x18), TXREG (0x19) and
+/// RCREG (0x1A).
+///
+/// Setting SPEN enables the port. A byte written to TXREG moves to the
+/// transmit shift register as soon as it is free and reaches the host one
+/// frame time later; TXIF is set while TXREG is empty. With CREN set, bytes
+/// from the host land in a two-byte FIFO read through RCREG, setting RCIF. A
+/// byte arriving with the FIFO full is lost and sets OERR, which stops
+/// reception until CREN is cleared. Only 8-bit frames are modelled.
+#[derive(Debug, Clone)]
+pub struct Uart {
+    /// Line speed in bits per second. The p16core has no baud rate generator
+    /// register, so the rate is set here; frame timing follows from it and
+    /// the core clock.
+    pub baud_rate: u32,
+    control: u8,
+    overrun: bool,
+    tx_reg: Option<u8>,
+    /// Byte being shifted out and the instruction cycles left.
+    tx_shift: Option<(u8, u64)>,
+    /// Byte being shifted in, its framing error and the cycles left.
+    rx_shift: Option<(u8, bool, u64)>,
+    rx_fifo: VecDeque<(u8, bool)>,
+    host: UartHost,
+}
+
+impl Default for Uart {
+    fn default() -> Self {
+        Self {
+            baud_rate: DEFAULT_BAUD_RATE,
+            control: 0,
+            overrun: false,
+            tx_reg: None,
+            tx_shift: None,
+            rx_shift: None,
+            rx_fifo: VecDeque::with_capacity(RX_FIFO_DEPTH),
+            host: UartHost::default(),
+        }
+    }
+}
+
+impl Uart {
+    /// A handle for exchanging bytes with the firmware.
+    pub fn host(&self) -> UartHost {
+        self.host.clone()
+    }
+
+    /// RCSTA as firmware reads it. FERR belongs to the byte at the head of
+    /// the FIFO.
+    pub fn rcsta(&self) -> u8 {
+        let ferr = self.rx_fifo.front().is_some_and(|&(_, ferr)| ferr);
+        self.control | if ferr { FERR } else { 0 } | if self.overrun { OERR } else { 0 }
+    }
+
+    fn enabled(&self) -> bool {
+        self.control & SPEN != 0
+    }
+
+    fn receiving(&self) -> bool {
+        self.enabled() && self.control & CREN != 0 && !self.overrun
+    }
+
+    /// Instruction cycles one frame takes at the current baud rate.
+    fn frame_cycles(&self, clock_hz: u32) -> u64 {
+        (FRAME_BITS * clock_hz as u64 / (4 * self.baud_rate.max(1) as u64)).max(1)
+    }
+
+    fn update_flags(&self, cx: &mut Context) {
+        if self.enabled() && self.tx_reg.is_none() {
+            cx.raise(Irq::Tx);
+        } else {
+            cx.lower(Irq::Tx);
+        }
+        if self.rx_fifo.is_empty() {
+            cx.lower(Irq::Rc);
+        } else {
+            cx.raise(Irq::Rc);
+        }
+    }
+
+    fn tick_transmit(&mut self, frame_cycles: u64) {
+        if let Some((byte, cycles)) = &mut self.tx_shift {
+            *cycles -= 1;
+            if *cycles == 0 {
+                self.host.lock().transmitted.push_back(*byte);
+                self.tx_shift = None;
+            }
+        }
+        if self.tx_shift.is_none()
+            && let Some(byte) = self.tx_reg.take()
+        {
+            self.tx_shift = Some((byte, frame_cycles));
+        }
+    }
+
+    fn tick_receive(&mut self, frame_cycles: u64) {
+        if self.rx_shift.is_none() {
+            if let Some((byte, ferr)) = self.host.lock().to_receive.pop_front() {
+                self.rx_shift = Some((byte, ferr, frame_cycles));
+            }
+            return;
+        }
+        if let Some((byte, ferr, cycles)) = &mut self.rx_shift {
+            *cycles -= 1;
+            if *cycles == 0 {
+                if self.rx_fifo.len() < RX_FIFO_DEPTH {
+                    self.rx_fifo.push_back((*byte, *ferr));
+                } else {
+                    self.overrun = true;
+                }
+                self.rx_shift = None;
+            }
+        }
+    }
+}
+
+impl Peripheral for Uart {
+    fn decodes(&self, address: u16) -> bool {
+        (0x018..=0x01A).contains(&address)
+    }
+
+    fn read(&mut self, address: u16, cx: &mut Context) -> u8 {
+        let value = match address {
+            RCSTA => self.rcsta(),
+            // TXREG is write-only.
+            TXREG => 0,
+            _ => self.rx_fifo.pop_front().map_or(0, |(byte, _)| byte),
+        };
+        self.update_flags(cx);
+        value
+    }
+
+    fn write(&mut self, address: u16, value: u8, cx: &mut Context) {
+        match address {
+            RCSTA => {
+                self.control = value & CONTROL_BITS;
+                if self.control & CREN == 0 {
+                    self.overrun = false;
+                }
+                if !self.enabled() {
+                    self.tx_shift = None;
+                    self.rx_shift = None;
+                }
+            }
+            TXREG => self.tx_reg = Some(value),
+            // RCREG is read-only.
+            _ => {}
+        }
+        self.update_flags(cx);
+    }
+
+    fn tick(&mut self, cx: &mut Context) {
+        let frame_cycles = self.frame_cycles(cx.clock_hz);
+        if self.enabled() {
+            self.tick_transmit(frame_cycles);
+        }
+        if self.receiving() {
+            self.tick_receive(frame_cycles);
+        }
+        self.update_flags(cx);
+    }
+
+    /// Every reset clears the registers and abandons frames in flight. The
+    /// baud rate and the host handle are kept.
+    fn reset(&mut self, _kind: ResetKind) {
+        *self = Self {
+            baud_rate: self.baud_rate,
+            host: self.host.clone(),
+            ..Self::default()
+        };
+    }
+}
@@ -0,0 +1,41 @@
+/// Nominal watchdog time-out period with no postscaler, in nanoseconds.
+pub const WDT_PERIOD_NS: u64 = 18_000_000;
+
+/// Watchdog timer running from its own RC oscillator.
+///
+/// Time is fed in nanoseconds rather than instruction cycles, so the time-out
+/// does not depend on the core clock.
+#[derive(Debug, Clone, Default)]
+pub struct Watchdog {
+    elapsed_ns: u64,
+    postscale_counter: u8,
+}
+
+impl Watchdog {
+    /// Restarts the time-out period and clears the postscaler.
+    pub fn clear(&mut self) {
+        self.elapsed_ns = 0;
+        self.postscale_counter = 0;
+    }
+
+    /// Advances the timer by `ns` nanoseconds with a postscale ratio of
+    /// `1:postscale`. Returns `true` when the watchdog times out.
+    pub fn tick(&mut self, ns: u64, postscale: u16) -> bool {
+        self.elapsed_ns += ns;
+        while self.elapsed_ns >= WDT_PERIOD_NS {
+            self.elapsed_ns -= WDT_PERIOD_NS;
+            self.postscale_counter += 1;
+            if self.postscale_counter as u16 >= postscale {
+                self.postscale_counter = 0;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Nanoseconds left until the next time-out at a postscale of `1:postscale`.
+    pub fn remaining_ns(&self, postscale: u16) -> u64 {
+        let periods = postscale.saturating_sub(1 + self.postscale_counter as u16) as u64;
+        periods * WDT_PERIOD_NS + (WDT_PERIOD_NS - self.elapsed_ns)
+    }
+}
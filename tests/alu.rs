@@ -0,0 +1,260 @@
+use p16core_sim::{Bit, Instruction, P16Core};
+
+const REG: u8 = 0x20;
+
+/// Expected state after one instruction. Flags given as `None` must be left
+/// unchanged.
+struct Case {
+    instruction: Instruction,
+    w: u8,
+    f: u8,
+    carry: bool,
+    expect_w: u8,
+    expect_f: u8,
+    z: Option<bool>,
+    dc: Option<bool>,
+    c: Option<bool>,
+    skip: bool,
+}
+
+macro_rules! case {
+    ($instruction:expr, w = $w:expr, f = $f:expr, c_in = $carry:expr
+        => w = $ew:expr, f = $ef:expr, z = $z:expr, dc = $dc:expr, c = $c:expr) => {
+        case!(@ $instruction, $w, $f, $carry, $ew, $ef, $z, $dc, $c, false)
+    };
+    ($instruction:expr, w = $w:expr, f = $f:expr, c_in = $carry:expr
+        => w = $ew:expr, f = $ef:expr, z = $z:expr, dc = $dc:expr, c = $c:expr, skip) => {
+        case!(@ $instruction, $w, $f, $carry, $ew, $ef, $z, $dc, $c, true)
+    };
+    (@ $instruction:expr, $w:expr, $f:expr, $carry:expr, $ew:expr, $ef:expr,
+        $z:expr, $dc:expr, $c:expr, $skip:expr) => {
+        Case {
+            instruction: $instruction,
+            w: $w,
+            f: $f,
+            carry: $carry,
+            expect_w: $ew,
+            expect_f: $ef,
+            z: $z,
+            dc: $dc,
+            c: $c,
+            skip: $skip,
+        }
+    };
+}
+
+/// Builds a file-register instruction storing its result back in `REG`.
+fn to_f(op: fn(u8, bool) -> Instruction) -> Instruction {
+    op(REG, true)
+}
+
+/// Builds a file-register instruction storing its result in W.
+fn to_w(op: fn(u8, bool) -> Instruction) -> Instruction {
+    op(REG, false)
+}
+
+fn cases() -> Vec<Case> {
+    use Instruction as I;
+    const T: Option<bool> = Some(true);
+    const F: Option<bool> = Some(false);
+    const U: Option<bool> = None;
+
+    let addwf = |reg, dest| I::ADDWF { reg, dest };
+    let andwf = |reg, dest| I::ANDWF { reg, dest };
+    let comf = |reg, dest| I::COMF { reg, dest };
+    let decf = |reg, dest| I::DECF { reg, dest };
+    let decfsz = |reg, dest| I::DECFSZ { reg, dest };
+    let incf = |reg, dest| I::INCF { reg, dest };
+    let incfsz = |reg, dest| I::INCFSZ { reg, dest };
+    let iorwf = |reg, dest| I::IORWF { reg, dest };
+    let movf = |reg, dest| I::MOVF { reg, dest };
+    let rlf = |reg, dest| I::RLF { reg, dest };
+    let rrf = |reg, dest| I::RRF { reg, dest };
+    let subwf = |reg, dest| I::SUBWF { reg, dest };
+    let swapf = |reg, dest| I::SWAPF { reg, dest };
+    let xorwf = |reg, dest| I::XORWF { reg, dest };
+
+    vec![
+        // ADDWF: Z, DC and C all follow the sum.
+        case!(to_f(addwf), w = 0x01, f = 0x02, c_in = false => w = 0x01, f = 0x03, z = F, dc = F, c = F),
+        case!(to_w(addwf), w = 0x0F, f = 0x01, c_in = false => w = 0x10, f = 0x01, z = F, dc = T, c = F),
+        case!(to_f(addwf), w = 0xF0, f = 0x20, c_in = false => w = 0xF0, f = 0x10, z = F, dc = F, c = T),
+        case!(to_f(addwf), w = 0x80, f = 0x80, c_in = false => w = 0x80, f = 0x00, z = T, dc = F, c = T),
+        case!(to_w(addwf), w = 0xFF, f = 0x01, c_in = false => w = 0x00, f = 0x01, z = T, dc = T, c = T),
+        // ADDLW
+        case!(I::ADDLW { lit: 0x08 }, w = 0x08, f = 0, c_in = true => w = 0x10, f = 0, z = F, dc = T, c = F),
+        case!(I::ADDLW { lit: 0x01 }, w = 0xFF, f = 0, c_in = false => w = 0x00, f = 0, z = T, dc = T, c = T),
+        // SUBWF: C and DC are set when no borrow occurs.
+        case!(to_f(subwf), w = 0x02, f = 0x03, c_in = false => w = 0x02, f = 0x01, z = F, dc = T, c = T),
+        case!(to_w(subwf), w = 0x02, f = 0x02, c_in = false => w = 0x00, f = 0x02, z = T, dc = T, c = T),
+        case!(to_f(subwf), w = 0x03, f = 0x02, c_in = true => w = 0x03, f = 0xFF, z = F, dc = F, c = F),
+        case!(to_w(subwf), w = 0x01, f = 0x10, c_in = false => w = 0x0F, f = 0x10, z = F, dc = F, c = T),
+        case!(to_w(subwf), w = 0x10, f = 0x01, c_in = true => w = 0xF1, f = 0x01, z = F, dc = T, c = F),
+        // SUBLW: k - W.
+        case!(I::SUBLW { lit: 0x03 }, w = 0x02, f = 0, c_in = false => w = 0x01, f = 0, z = F, dc = T, c = T),
+        case!(I::SUBLW { lit: 0x02 }, w = 0x02, f = 0, c_in = false => w = 0x00, f = 0, z = T, dc = T, c = T),
+        case!(I::SUBLW { lit: 0x02 }, w = 0x03, f = 0, c_in = true => w = 0xFF, f = 0, z = F, dc = F, c = F),
+        // ANDWF: Z follows the result wherever it is stored.
+        case!(to_f(andwf), w = 0xFF, f = 0x00, c_in = false => w = 0xFF, f = 0x00, z = T, dc = U, c = U),
+        case!(to_f(andwf), w = 0x00, f = 0xFF, c_in = false => w = 0x00, f = 0x00, z = T, dc = U, c = U),
+        case!(to_f(andwf), w = 0x0F, f = 0x3C, c_in = false => w = 0x0F, f = 0x0C, z = F, dc = U, c = U),
+        case!(to_w(andwf), w = 0xF0, f = 0x0F, c_in = false => w = 0x00, f = 0x0F, z = T, dc = U, c = U),
+        case!(I::ANDLW { lit: 0x0F }, w = 0xF3, f = 0, c_in = false => w = 0x03, f = 0, z = F, dc = U, c = U),
+        case!(I::ANDLW { lit: 0x0F }, w = 0xF0, f = 0, c_in = false => w = 0x00, f = 0, z = T, dc = U, c = U),
+        // IORWF / IORLW
+        case!(to_f(iorwf), w = 0x01, f = 0x10, c_in = false => w = 0x01, f = 0x11, z = F, dc = U, c = U),
+        case!(to_w(iorwf), w = 0x00, f = 0x00, c_in = false => w = 0x00, f = 0x00, z = T, dc = U, c = U),
+        case!(I::IORLW { lit: 0x00 }, w = 0x00, f = 0, c_in = false => w = 0x00, f = 0, z = T, dc = U, c = U),
+        case!(I::IORLW { lit: 0x80 }, w = 0x01, f = 0, c_in = false => w = 0x81, f = 0, z = F, dc = U, c = U),
+        // XORWF / XORLW
+        case!(to_f(xorwf), w = 0x5A, f = 0x5A, c_in = false => w = 0x5A, f = 0x00, z = T, dc = U, c = U),
+        case!(to_w(xorwf), w = 0x5A, f = 0xFF, c_in = false => w = 0xA5, f = 0xFF, z = F, dc = U, c = U),
+        case!(I::XORLW { lit: 0xFF }, w = 0xFF, f = 0, c_in = false => w = 0x00, f = 0, z = T, dc = U, c = U),
+        case!(I::XORLW { lit: 0x0F }, w = 0xF0, f = 0, c_in = false => w = 0xFF, f = 0, z = F, dc = U, c = U),
+        // CLRF / CLRW
+        case!(I::CLRF { reg: REG }, w = 0x12, f = 0x34, c_in = false => w = 0x12, f = 0x00, z = T, dc = U, c = U),
+        case!(I::CLRW, w = 0x12, f = 0x34, c_in = false => w = 0x00, f = 0x34, z = T, dc = U, c = U),
+        // COMF
+        case!(to_f(comf), w = 0, f = 0x0F, c_in = false => w = 0, f = 0xF0, z = F, dc = U, c = U),
+        case!(to_w(comf), w = 0, f = 0xFF, c_in = false => w = 0x00, f = 0xFF, z = T, dc = U, c = U),
+        // MOVF sets Z, including the `movf f,f` register test.
+        case!(to_f(movf), w = 0x55, f = 0x00, c_in = false => w = 0x55, f = 0x00, z = T, dc = U, c = U),
+        case!(to_f(movf), w = 0x55, f = 0x01, c_in = false => w = 0x55, f = 0x01, z = F, dc = U, c = U),
+        case!(to_w(movf), w = 0x55, f = 0x00, c_in = false => w = 0x00, f = 0x00, z = T, dc = U, c = U),
+        case!(to_w(movf), w = 0x00, f = 0x80, c_in = false => w = 0x80, f = 0x80, z = F, dc = U, c = U),
+        // MOVWF and MOVLW leave flags alone.
+        case!(I::MOVWF { reg: REG }, w = 0x00, f = 0xAA, c_in = false => w = 0x00, f = 0x00, z = U, dc = U, c = U),
+        case!(I::MOVLW { lit: 0x00 }, w = 0x12, f = 0, c_in = false => w = 0x00, f = 0, z = U, dc = U, c = U),
+        // DECF / INCF wrap around.
+        case!(to_f(decf), w = 0, f = 0x01, c_in = false => w = 0, f = 0x00, z = T, dc = U, c = U),
+        case!(to_f(decf), w = 0, f = 0x00, c_in = false => w = 0, f = 0xFF, z = F, dc = U, c = U),
+        case!(to_w(decf), w = 0, f = 0x10, c_in = false => w = 0x0F, f = 0x10, z = F, dc = U, c = U),
+        case!(to_f(incf), w = 0, f = 0xFF, c_in = false => w = 0, f = 0x00, z = T, dc = U, c = U),
+        case!(to_f(incf), w = 0, f = 0x0F, c_in = false => w = 0, f = 0x10, z = F, dc = U, c = U),
+        case!(to_w(incf), w = 0, f = 0xFF, c_in = false => w = 0x00, f = 0xFF, z = T, dc = U, c = U),
+        // DECFSZ / INCFSZ skip on zero and leave flags alone.
+        case!(to_f(decfsz), w = 0, f = 0x02, c_in = false => w = 0, f = 0x01, z = U, dc = U, c = U),
+        case!(to_f(decfsz), w = 0, f = 0x01, c_in = false => w = 0, f = 0x00, z = U, dc = U, c = U, skip),
+        case!(to_f(decfsz), w = 0, f = 0x00, c_in = false => w = 0, f = 0xFF, z = U, dc = U, c = U),
+        case!(to_w(decfsz), w = 0, f = 0x01, c_in = false => w = 0x00, f = 0x01, z = U, dc = U, c = U, skip),
+        case!(to_f(incfsz), w = 0, f = 0xFE, c_in = false => w = 0, f = 0xFF, z = U, dc = U, c = U),
+        case!(to_f(incfsz), w = 0, f = 0xFF, c_in = false => w = 0, f = 0x00, z = U, dc = U, c = U, skip),
+        case!(to_w(incfsz), w = 0, f = 0xFF, c_in = false => w = 0x00, f = 0xFF, z = U, dc = U, c = U, skip),
+        // RLF / RRF rotate through carry, into W when d = 0.
+        case!(to_f(rlf), w = 0, f = 0x81, c_in = false => w = 0, f = 0x02, z = U, dc = U, c = T),
+        case!(to_f(rlf), w = 0, f = 0x01, c_in = true => w = 0, f = 0x03, z = U, dc = U, c = F),
+        case!(to_w(rlf), w = 0, f = 0x40, c_in = true => w = 0x81, f = 0x40, z = U, dc = U, c = F),
+        case!(to_w(rlf), w = 0, f = 0x80, c_in = false => w = 0x00, f = 0x80, z = U, dc = U, c = T),
+        case!(to_f(rrf), w = 0, f = 0x81, c_in = false => w = 0, f = 0x40, z = U, dc = U, c = T),
+        case!(to_f(rrf), w = 0, f = 0x02, c_in = true => w = 0, f = 0x81, z = U, dc = U, c = F),
+        case!(to_w(rrf), w = 0, f = 0x01, c_in = false => w = 0x00, f = 0x01, z = U, dc = U, c = T),
+        // SWAPF
+        case!(to_f(swapf), w = 0, f = 0x12, c_in = false => w = 0, f = 0x21, z = U, dc = U, c = U),
+        case!(to_w(swapf), w = 0, f = 0xA5, c_in = false => w = 0x5A, f = 0xA5, z = U, dc = U, c = U),
+        // BCF / BSF
+        case!(I::BCF { reg: REG, bit: Bit::B7 }, w = 0, f = 0xFF, c_in = false => w = 0, f = 0x7F, z = U, dc = U, c = U),
+        case!(I::BSF { reg: REG, bit: Bit::B0 }, w = 0, f = 0x00, c_in = false => w = 0, f = 0x01, z = U, dc = U, c = U),
+        // BTFSC / BTFSS test every bit, not only bit 0.
+        case!(I::BTFSC { reg: REG, bit: Bit::B0 }, w = 0, f = 0x01, c_in = false => w = 0, f = 0x01, z = U, dc = U, c = U),
+        case!(I::BTFSC { reg: REG, bit: Bit::B0 }, w = 0, f = 0xFE, c_in = false => w = 0, f = 0xFE, z = U, dc = U, c = U, skip),
+        case!(I::BTFSC { reg: REG, bit: Bit::B5 }, w = 0, f = 0x20, c_in = false => w = 0, f = 0x20, z = U, dc = U, c = U),
+        case!(I::BTFSC { reg: REG, bit: Bit::B5 }, w = 0, f = 0xDF, c_in = false => w = 0, f = 0xDF, z = U, dc = U, c = U, skip),
+        case!(I::BTFSS { reg: REG, bit: Bit::B0 }, w = 0, f = 0x01, c_in = false => w = 0, f = 0x01, z = U, dc = U, c = U, skip),
+        case!(I::BTFSS { reg: REG, bit: Bit::B0 }, w = 0, f = 0xFE, c_in = false => w = 0, f = 0xFE, z = U, dc = U, c = U),
+        case!(I::BTFSS { reg: REG, bit: Bit::B7 }, w = 0, f = 0x80, c_in = false => w = 0, f = 0x80, z = U, dc = U, c = U, skip),
+        case!(I::BTFSS { reg: REG, bit: Bit::B7 }, w = 0, f = 0x7F, c_in = false => w = 0, f = 0x7F, z = U, dc = U, c = U),
+    ]
+}
+
+/// Runs `case` once with Z and DC preset to `preset`, so that flags the
+/// instruction must not touch are caught whichever way they start.
+fn run(case: &Case, preset: bool) -> Result<(), String> {
+    let mut core = P16Core::default();
+    core.pc = 0x100;
+    core.w = case.w;
+    core.write(REG as u16, case.f);
+    core.status.z = preset;
+    core.status.dc = preset;
+    core.status.c = case.carry;
+
+    let cycles = core.exec_op(case.instruction);
+
+    let mut errors = Vec::new();
+    let mut check = |what: &str, found: String, expected: String| {
+        if found != expected {
+            errors.push(format!("{what}: expected {expected}, found {found}"));
+        }
+    };
+    check(
+        "W",
+        format!("{:#04x}", core.w),
+        format!("{:#04x}", case.expect_w),
+    );
+    check(
+        "f",
+        format!("{:#04x}", core.read(REG as u16)),
+        format!("{:#04x}", case.expect_f),
+    );
+    check(
+        "Z",
+        core.status.z.to_string(),
+        case.z.unwrap_or(preset).to_string(),
+    );
+    check(
+        "DC",
+        core.status.dc.to_string(),
+        case.dc.unwrap_or(preset).to_string(),
+    );
+    check(
+        "C",
+        core.status.c.to_string(),
+        case.c.unwrap_or(case.carry).to_string(),
+    );
+    let (pc, expected_cycles) = if case.skip { (0x101, 2) } else { (0x100, 1) };
+    check("PC", format!("{:#x}", core.pc), format!("{pc:#x}"));
+    check("cycles", cycles.to_string(), expected_cycles.to_string());
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{:?} w={:#04x} f={:#04x} c={} (flags preset {preset}): {}",
+            case.instruction,
+            case.w,
+            case.f,
+            case.carry,
+            errors.join(", ")
+        ))
+    }
+}
+
+#[test]
+fn alu_instructions_follow_the_datasheet() {
+    let failures: Vec<String> = cases()
+        .iter()
+        .flat_map(|case| [run(case, false), run(case, true)])
+        .filter_map(Result::err)
+        .collect();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn flags_win_when_the_destination_is_status() {
+    let mut core = P16Core::default();
+    core.w = 0xFF;
+    core.status.set(0x18);
+    core.exec_op(Instruction::ANDWF {
+        reg: 0x03,
+        dest: true,
+    });
+    // 0x18 & 0xFF is non-zero, so Z stays clear after the write.
+    assert!(!core.status.z);
+
+    core.w = 0x00;
+    core.exec_op(Instruction::ANDWF {
+        reg: 0x03,
+        dest: true,
+    });
+    assert!(core.status.z);
+}
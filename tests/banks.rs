@@ -0,0 +1,43 @@
+use p16core_sim::P16Core;
+
+fn select_bank(core: &mut P16Core, bank: u16) {
+    core.status.rp1 = bank & 2 != 0;
+    core.status.rp0 = bank & 1 != 0;
+}
+
+#[test]
+fn rp_bits_select_128_byte_banks() {
+    let mut core = P16Core::default();
+    for bank in 0..4 {
+        select_bank(&mut core, bank);
+        core.write(0x20, 0x10 + bank as u8);
+        core.write(0x6F, 0x20 + bank as u8);
+    }
+
+    for bank in 1..4 {
+        assert_eq!(
+            core.read(bank << 7 | 0x20),
+            0x10 + bank as u8,
+            "bank {bank}"
+        );
+        assert_eq!(
+            core.read(bank << 7 | 0x6F),
+            0x20 + bank as u8,
+            "bank {bank}"
+        );
+    }
+    select_bank(&mut core, 0);
+    assert_eq!(core.read(0x20), 0x10);
+    assert_eq!(core.read(0x6F), 0x20);
+}
+
+#[test]
+fn rp0_reaches_bank_1_registers() {
+    let mut core = P16Core::default();
+    select_bank(&mut core, 1);
+    core.write(0x0C, 0x21);
+    assert_eq!(core.read(0x8C), 0x21, "PIE1");
+
+    select_bank(&mut core, 0);
+    assert_eq!(core.read(0x0C), 0x00, "PIR1");
+}
@@ -0,0 +1,29 @@
+//! Fixtures shared by the integration tests. Each test crate uses only some
+//! of them.
+#![allow(dead_code)]
+
+use p16core_sim::{P16Core, asm};
+
+/// Assembles `source` and loads it into a fresh core.
+pub fn assemble_core(source: &str) -> P16Core {
+    assemble_with_symbols(source).0
+}
+
+/// Like [`assemble_core`], also returning the assembly for its symbols.
+pub fn assemble_with_symbols(source: &str) -> (P16Core, asm::Assembly) {
+    let assembly = asm::assemble(source).unwrap();
+    (P16Core::from_image(assembly.image.clone()), assembly)
+}
+
+/// Steps `core` until its cycle count reaches `cycles`.
+pub fn run_until(core: &mut P16Core, cycles: u64) {
+    while core.cycles() < cycles {
+        core.step();
+    }
+}
+
+/// Steps `core` for at least `cycles` more instruction cycles.
+pub fn run_for(core: &mut P16Core, cycles: u64) {
+    let end = core.cycles() + cycles;
+    run_until(core, end);
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use common::{assemble_core, assemble_with_symbols};
+use p16core_sim::P16Core;
+
+/// Assembles `body` at 0 followed by a subroutine `sub` that just returns.
+fn core(body: &str) -> P16Core {
+    let source = format!(
+        "  #include p16core.inc
+  org 0
+{body}
+done
+  goto done
+sub
+  return
+  end
+"
+    );
+    assemble_core(&source)
+}
+
+/// Steps once per expected count, checking what each instruction took.
+fn assert_cycles(core: &mut P16Core, expected: &[u8]) {
+    for (i, &cycles) in expected.iter().enumerate() {
+        let start = core.cycles();
+        assert_eq!(core.step(), cycles, "instruction {i} at 0x{:03X}", core.pc);
+        assert_eq!(core.cycles() - start, cycles as u64);
+    }
+}
+
+#[test]
+fn branches_take_two_cycles() {
+    let mut core = core(
+        "  nop
+  goto next
+next
+  call sub
+  movlw 0x01
+  call table
+  goto done
+table
+  retlw 0x2A",
+    );
+    // nop, goto, call, return, movlw, call, retlw, goto
+    assert_cycles(&mut core, &[1, 2, 2, 2, 1, 2, 2, 2]);
+    assert_eq!(core.w, 0x2A);
+}
+
+#[test]
+fn skips_take_two_cycles_only_when_taken() {
+    let mut core = core(
+        "  movlw 0x02
+  movwf 0x20
+  decfsz 0x20,f
+  nop
+  decfsz 0x20,f
+  nop
+  btfss STATUS,Z
+  nop
+  btfsc STATUS,Z
+  nop
+  incfsz 0x20,f
+  nop",
+    );
+    // decfsz falls through to 1, then skips at 0. Z is untouched by either,
+    // so btfss runs the nop and btfsc skips it.
+    assert_cycles(&mut core, &[1, 1, 1, 1, 2, 1, 1, 2, 1, 1]);
+    assert_eq!(core.read(0x20), 1);
+}
+
+#[test]
+fn writes_to_pcl_take_two_cycles() {
+    let mut core = core(
+        "  movlw 0x04
+  movwf PCL
+  nop
+  nop
+  movlw 0x02
+  addwf PCL,f
+  nop
+  nop
+  clrf 0x20
+  incf PCL,f
+  nop
+  movf PCL,w
+  bsf PCL,0",
+    );
+    // movlw, movwf PCL -> 0x04, movlw, addwf PCL -> 0x08, clrf, incf PCL
+    // -> 0x0B, movf PCL (a read), bsf PCL -> 0x0D.
+    assert_cycles(&mut core, &[1, 2, 1, 2, 1, 2, 1, 2]);
+    assert_eq!(core.pc, 0x0D);
+}
+
+#[test]
+fn interrupt_entry_adds_two_cycles() {
+    let mut core = core(
+        "  goto main
+  org 4
+  bcf INTCON,T0IF
+  retfie
+main
+  movlw 0xA0
+  movwf INTCON
+  bsf INTCON,T0IF",
+    );
+    assert_cycles(&mut core, &[2, 1, 1, 1 + 2, 1, 2]);
+    assert_eq!(core.pc, 0x0009, "back after the bsf");
+}
+
+#[test]
+fn delay_loop_takes_the_documented_time() {
+    let source = "  #include p16core.inc
+  org 0
+  call delay
+done
+  goto done
+; 3 * 0xC8 + 5 cycles including the call and return.
+delay
+  movlw 0xC8
+  movwf 0x70
+wait
+  decfsz 0x70,f
+  goto wait
+  return
+  end
+";
+    let (mut core, assembly) = assemble_with_symbols(source);
+    let done = assembly.symbols["done"] as u16;
+    let mut total = 0u64;
+    while core.pc != done {
+        total += core.step() as u64;
+    }
+    assert_eq!(total, 3 * 200 + 5);
+    assert_eq!(core.cycles(), total);
+}
@@ -0,0 +1,195 @@
+use p16core_sim::{
+    Halt, Instruction, InvalidOpcodePolicy, P16Core,
+    exec::{decode, encode},
+};
+
+/// Mid-range opcode map as `(mask, pattern, mnemonic)`. A word matches an
+/// entry when `word & mask == pattern`; words matching no entry are reserved
+/// and must decode to `Instruction::Invalid`.
+const OPCODES: &[(u16, u16, &str)] = &[
+    (0x3F00, 0x0700, "ADDWF"),
+    (0x3F00, 0x0500, "ANDWF"),
+    (0x3F80, 0x0180, "CLRF"),
+    (0x3F80, 0x0100, "CLRW"),
+    (0x3F00, 0x0900, "COMF"),
+    (0x3F00, 0x0300, "DECF"),
+    (0x3F00, 0x0B00, "DECFSZ"),
+    (0x3F00, 0x0A00, "INCF"),
+    (0x3F00, 0x0F00, "INCFSZ"),
+    (0x3F00, 0x0400, "IORWF"),
+    (0x3F00, 0x0800, "MOVF"),
+    (0x3F80, 0x0080, "MOVWF"),
+    (0x3F9F, 0x0000, "NOP"),
+    (0x3F00, 0x0D00, "RLF"),
+    (0x3F00, 0x0C00, "RRF"),
+    (0x3F00, 0x0200, "SUBWF"),
+    (0x3F00, 0x0E00, "SWAPF"),
+    (0x3F00, 0x0600, "XORWF"),
+    (0x3C00, 0x1000, "BCF"),
+    (0x3C00, 0x1400, "BSF"),
+    (0x3C00, 0x1800, "BTFSC"),
+    (0x3C00, 0x1C00, "BTFSS"),
+    (0x3E00, 0x3E00, "ADDLW"),
+    (0x3F00, 0x3900, "ANDLW"),
+    (0x3800, 0x2000, "CALL"),
+    (0x3FFF, 0x0064, "CLRWDT"),
+    (0x3800, 0x2800, "GOTO"),
+    (0x3F00, 0x3800, "IORLW"),
+    (0x3C00, 0x3000, "MOVLW"),
+    (0x3FFF, 0x0009, "RETFIE"),
+    (0x3C00, 0x3400, "RETLW"),
+    (0x3FFF, 0x0008, "RETURN"),
+    (0x3FFF, 0x0063, "SLEEP"),
+    (0x3E00, 0x3C00, "SUBLW"),
+    (0x3F00, 0x3A00, "XORLW"),
+];
+
+fn reference_entry(word: u16) -> Option<&'static (u16, u16, &'static str)> {
+    let mut matches = OPCODES
+        .iter()
+        .filter(|(mask, pattern, _)| word & mask == *pattern);
+    let found = matches.next();
+    assert!(
+        matches.next().is_none(),
+        "opcode table overlaps at {word:#06x}"
+    );
+    found
+}
+
+fn reference(word: u16) -> Option<&'static str> {
+    reference_entry(word).map(|(_, _, name)| *name)
+}
+
+fn mnemonic(instruction: &Instruction) -> String {
+    let debug = format!("{instruction:?}");
+    debug
+        .split([' ', '{', '('])
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Checks the operand fields of `instruction` against the bits of `word`.
+fn operands_match(word: u16, instruction: Instruction) -> bool {
+    let f = (word & 0x7F) as u8;
+    let d = word & 0x80 != 0;
+    let b = ((word >> 7) & 0x7) as u8;
+    let k = (word & 0xFF) as u8;
+    match instruction {
+        Instruction::ADDWF { reg, dest }
+        | Instruction::ANDWF { reg, dest }
+        | Instruction::COMF { reg, dest }
+        | Instruction::DECF { reg, dest }
+        | Instruction::DECFSZ { reg, dest }
+        | Instruction::INCF { reg, dest }
+        | Instruction::INCFSZ { reg, dest }
+        | Instruction::IORWF { reg, dest }
+        | Instruction::MOVF { reg, dest }
+        | Instruction::RLF { reg, dest }
+        | Instruction::RRF { reg, dest }
+        | Instruction::SUBWF { reg, dest }
+        | Instruction::SWAPF { reg, dest }
+        | Instruction::XORWF { reg, dest } => reg == f && dest == d,
+        Instruction::CLRF { reg } | Instruction::MOVWF { reg } => reg == f,
+        Instruction::BCF { reg, bit }
+        | Instruction::BSF { reg, bit }
+        | Instruction::BTFSC { reg, bit }
+        | Instruction::BTFSS { reg, bit } => reg == f && bit.as_u8() == b,
+        Instruction::ADDLW { lit }
+        | Instruction::ANDLW { lit }
+        | Instruction::IORLW { lit }
+        | Instruction::MOVLW { lit }
+        | Instruction::RETLW { lit }
+        | Instruction::SUBLW { lit }
+        | Instruction::XORLW { lit } => lit == k,
+        Instruction::CALL { lit } | Instruction::GOTO { lit } => lit == word & 0x7FF,
+        Instruction::CLRW
+        | Instruction::CLRWDT
+        | Instruction::NOP
+        | Instruction::RETFIE
+        | Instruction::RETURN
+        | Instruction::SLEEP => true,
+        Instruction::Invalid(invalid) => invalid == word,
+    }
+}
+
+#[test]
+fn decodes_every_opcode_word() {
+    let mut failures = Vec::new();
+    for word in 0..=0x3FFFu16 {
+        let expected = reference(word);
+        let decoded = decode(word);
+        let ok = match expected {
+            None => matches!(decoded, Instruction::Invalid(_)) && operands_match(word, decoded),
+            Some(name) => mnemonic(&decoded) == name && operands_match(word, decoded),
+        };
+        if !ok {
+            failures.push(format!(
+                "{word:#06x}: expected {expected:?}, got {decoded:?}"
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn encode_round_trips_every_opcode_word() {
+    let mut failures = Vec::new();
+    for word in 0..=0x3FFFu16 {
+        let instruction = decode(word);
+        let encoded = encode(instruction);
+
+        let same_opcode = match reference_entry(word) {
+            Some((mask, pattern, _)) => encoded & mask == *pattern,
+            None => encoded == word,
+        };
+        // Operand bits must survive; only don't-care bits may differ.
+        if !same_opcode || decode(encoded) != instruction {
+            failures.push(format!(
+                "{word:#06x}: {instruction:?} encoded as {encoded:#06x}"
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn words_wider_than_14_bits_are_invalid() {
+    for word in [0x4000, 0x4064, 0x8000, 0xFFFF] {
+        assert_eq!(decode(word), Instruction::Invalid(word), "{word:#06x}");
+    }
+}
+
+#[test]
+fn stepping_over_a_wide_word_follows_the_policy() {
+    let core_with = |policy| {
+        let mut core = P16Core::default();
+        core.invalid_opcode_policy = policy;
+        core.program_mut()[0] = 0x3005; // movlw 5
+        core.program_mut()[1] = 0x4000;
+        core.step();
+        core.step();
+        core
+    };
+
+    let core = core_with(InvalidOpcodePolicy::Halt);
+    assert_eq!(
+        core.halted(),
+        Some(&Halt::InvalidOpcode {
+            pc: 1,
+            word: 0x4000
+        })
+    );
+    assert_eq!(core.pc, 1);
+
+    let core = core_with(InvalidOpcodePolicy::Nop);
+    assert_eq!(core.halted(), None);
+    assert_eq!(core.pc, 2);
+    assert_eq!(core.w, 5);
+
+    let core = core_with(InvalidOpcodePolicy::Reset);
+    assert_eq!(core.halted(), None);
+    assert_eq!(core.pc, 0);
+}
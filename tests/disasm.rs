@@ -0,0 +1,83 @@
+use p16core_sim::{
+    asm,
+    disasm::{format, listing, register_name},
+    exec::{Instruction, decode},
+};
+
+#[test]
+fn special_function_registers_print_by_name_on_request() {
+    let bcf = decode(0x138B);
+    assert_eq!(format(bcf, true), "bcf INTCON,7");
+    assert_eq!(format(bcf, false), "bcf 0x0B,7");
+    assert_eq!(bcf.to_string(), "bcf 0x0B,7", "Display never uses names");
+
+    // General-purpose registers and unnamed SFR slots stay hex.
+    assert_eq!(format(decode(0x00A0), true), "movwf 0x20");
+    assert_eq!(format(decode(0x0809), true), "movf 0x09,w");
+    assert_eq!(register_name(0x09), None);
+    assert_eq!(register_name(0x1C), Some("PTR1L"));
+    assert_eq!(register_name(0x20), None);
+}
+
+#[test]
+fn literals_and_targets_print_as_hex() {
+    assert_eq!(decode(0x3005).to_string(), "movlw 0x05");
+    assert_eq!(decode(0x2810).to_string(), "goto 0x010");
+    assert_eq!(decode(0x27FF).to_string(), "call 0x7FF");
+    assert_eq!(decode(0x0008).to_string(), "return");
+}
+
+#[test]
+fn invalid_words_print_as_data() {
+    let word = decode(0x4000);
+    assert!(matches!(word, Instruction::Invalid(0x4000)));
+    assert_eq!(word.to_string(), "dw 0x4000");
+    assert_eq!(format(decode(0x0061), true), "dw 0x0061");
+}
+
+#[test]
+fn listing_trims_trailing_unprogrammed_words() {
+    let program = [0x3005, 0x0000, 0x0086, 0x0000, 0x0000];
+    assert_eq!(
+        listing(&program, true),
+        "0x0000  3005  movlw 0x05\n\
+         0x0001  0000  nop\n\
+         0x0002  0086  movwf PORTB\n"
+    );
+    assert_eq!(listing(&[0; 16], false), "");
+}
+
+#[test]
+fn listing_reassembles_to_the_same_words() {
+    let source = "  #include p16core.inc
+  org 0
+  goto main
+  org 4
+  retfie
+main
+  bsf STATUS,RP0
+  clrf TRISB
+  bcf STATUS,RP0
+loop
+  incf PORTB,f
+  btfss STATUS,Z
+  goto loop
+  call sub
+  sleep
+sub
+  retlw 0x2A
+  end
+";
+    let program = asm::assemble(source).unwrap().image.program;
+    let text = listing(&program, true);
+
+    let mut source = String::from("  #include p16core.inc\n");
+    for line in text.lines() {
+        let (address, rest) = line.split_once("  ").unwrap();
+        let instruction = &rest[6..];
+        source += &format!("  org {address}\n  {instruction}\n");
+    }
+    source += "  end\n";
+    let reassembled = asm::assemble(&source).unwrap().image.program;
+    assert_eq!(reassembled, program);
+}
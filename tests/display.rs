@@ -0,0 +1,132 @@
+mod common;
+
+use common::assemble_core;
+use p16core_sim::{
+    P16Core,
+    display::{Display, MAX_DIGITS},
+};
+
+const DAN: u16 = 0x13;
+const DSEG: u16 = 0x14;
+
+/// Multiplexes "12.34" across four digits: blank, select the digit, drive
+/// its segments and hold them for a while.
+const MULTIPLEX: &str = "  #include p16core.inc
+  org 0
+main
+  clrf DSEG
+  movlw 0x01
+  movwf DAN
+  movlw 0x06
+  movwf DSEG
+  call hold
+  clrf DSEG
+  movlw 0x02
+  movwf DAN
+  movlw 0xDB
+  movwf DSEG
+  call hold
+  clrf DSEG
+  movlw 0x04
+  movwf DAN
+  movlw 0x4F
+  movwf DSEG
+  call hold
+  clrf DSEG
+  movlw 0x08
+  movwf DAN
+  movlw 0x66
+  movwf DSEG
+  call hold
+  goto main
+hold
+  movlw 0xC8
+  movwf 0x70
+wait
+  decfsz 0x70,f
+  goto wait
+  return
+  end
+";
+
+fn display(core: &P16Core) -> &Display {
+    core.bus().get::<Display>().unwrap()
+}
+
+#[test]
+fn multiplexed_digits_read_as_text() {
+    let mut core = assemble_core(MULTIPLEX);
+    assert_eq!(display(&core).text(), "    ");
+
+    // Two 20 ms persistence windows at 5 MIPS.
+    while core.cycles() < 200_000 {
+        core.step();
+    }
+    assert_eq!(display(&core).text(), "12.34");
+
+    // Each digit is lit for about a quarter of the time.
+    let brightness = display(&core).brightness(0);
+    assert!((0.2..0.3).contains(&brightness[1]), "{brightness:?}");
+    assert_eq!(brightness[0], 0.0);
+}
+
+#[test]
+fn glitches_between_digits_are_not_seen() {
+    let mut core = P16Core::default();
+    // Digit 1 shows '8' for one cycle of every 1000 before DSEG catches up.
+    for _ in 0..200 {
+        core.write(DAN, 0x02);
+        core.write(DSEG, 0x7F);
+        core.step();
+        core.write(DAN, 0x01);
+        core.write(DSEG, 0x3F);
+        for _ in 0..999 {
+            core.step();
+        }
+    }
+    assert_eq!(display(&core).text(), "0   ");
+    assert_eq!(display(&core).segments(1), 0);
+}
+
+#[test]
+fn unknown_patterns_show_as_question_marks() {
+    let mut core = P16Core::default();
+    core.write(DAN, 0x0F);
+    core.write(DSEG, 0x49);
+    while core.cycles() < 100_000 {
+        core.step();
+    }
+    assert_eq!(display(&core).text(), "????");
+}
+
+#[test]
+fn every_dan_bit_is_a_digit() {
+    let mut core = P16Core::default();
+    core.write(DAN, 0x80);
+    core.write(DSEG, 0x06);
+    while core.cycles() < 100_000 {
+        core.step();
+    }
+    assert_eq!(display(&core).segments(MAX_DIGITS - 1), 0x06);
+    assert_eq!(display(&core).text(), "    ", "only four digits are wired");
+}
+
+#[test]
+#[should_panic(expected = "digit 8 out of range")]
+fn digits_past_max_digits_panic() {
+    P16Core::default()
+        .bus()
+        .get::<Display>()
+        .unwrap()
+        .brightness(MAX_DIGITS);
+}
+
+#[test]
+#[should_panic(expected = "digit 8 out of range")]
+fn segments_past_max_digits_panic() {
+    P16Core::default()
+        .bus()
+        .get::<Display>()
+        .unwrap()
+        .segments(MAX_DIGITS);
+}
@@ -0,0 +1,172 @@
+mod common;
+
+use common::{assemble_core, assemble_with_symbols};
+use p16core_sim::{P16Core, Port, ResetKind, ports::Ports};
+
+const PORTA: u16 = 0x05;
+const PORTB: u16 = 0x06;
+const INTCON: u16 = 0x0B;
+const OPTION_REG: u16 = 0x81;
+const TRISA: u16 = 0x85;
+const TRISB: u16 = 0x86;
+
+const INTF: u8 = 1 << 1;
+const RBIF: u8 = 1 << 0;
+
+fn output(core: &P16Core, port: Port, pin: u8) -> Option<bool> {
+    core.bus().get::<Ports>().unwrap().output(port, pin)
+}
+
+#[test]
+fn tris_selects_between_latch_and_driven_level() {
+    let mut core = P16Core::default();
+    assert_eq!(core.read(TRISB), 0xFF, "pins start as inputs");
+
+    core.write(PORTB, 0xA5);
+    core.drive_pin(Port::B, 0, Some(false));
+    core.drive_pin(Port::B, 6, Some(true));
+    assert_eq!(core.read(PORTB), 0x40);
+
+    core.write(TRISB, 0x0F);
+    assert_eq!(core.read(PORTB), 0xA0);
+    assert!(core.pin(Port::B, 7));
+    assert_eq!(output(&core, Port::B, 7), Some(true));
+    assert_eq!(output(&core, Port::B, 6), Some(false));
+    assert_eq!(output(&core, Port::B, 0), None);
+
+    // A driven output pin still shows its latch.
+    core.drive_pin(Port::B, 4, Some(true));
+    assert!(!core.pin(Port::B, 4));
+}
+
+#[test]
+fn portb_pull_ups_follow_rbpu() {
+    let mut core = P16Core::default();
+    core.drive_pin(Port::B, 1, Some(false));
+    assert_eq!(core.read(PORTB), 0x00);
+    assert_eq!(core.read(PORTA), 0x00);
+
+    core.write(OPTION_REG, 0x7F);
+    assert_eq!(core.read(PORTB), 0xFD);
+    assert_eq!(core.read(PORTA), 0x00, "only PORTB has pull-ups");
+
+    // Pull-ups are off for output pins.
+    core.write(TRISB, 0x0F);
+    assert_eq!(core.read(PORTB), 0x0D);
+
+    core.drive_pin(Port::B, 1, None);
+    assert_eq!(core.read(PORTB), 0x0F);
+}
+
+#[test]
+fn bsf_copies_input_levels_into_the_latch() {
+    let source = "  #include p16core.inc
+  org 0
+  movlw 0x01
+  movwf PORTB
+  bsf PORTB,1
+  bsf STATUS,RP0
+  clrf TRISB
+  bcf STATUS,RP0
+done
+  goto done
+  end
+";
+    let (mut core, assembly) = assemble_with_symbols(source);
+    let done = assembly.symbols["done"] as u16;
+    core.drive_pin(Port::B, 0, Some(false));
+
+    while core.pc != done {
+        core.step();
+    }
+    // RB0 was an input held low when BSF read PORTB, so its latch lost the 1.
+    assert_eq!(output(&core, Port::B, 0), Some(false));
+    assert_eq!(output(&core, Port::B, 1), Some(true));
+    assert_eq!(core.read(PORTB), 0x02);
+}
+
+#[test]
+fn firmware_drives_outputs() {
+    let source = "  #include p16core.inc
+  org 0
+  bsf STATUS,RP0
+  movlw 0xF0
+  movwf TRISA
+  bcf STATUS,RP0
+loop
+  incf PORTA,f
+  goto loop
+  end
+";
+    let mut core = assemble_core(source);
+    for _ in 0..8 {
+        core.step();
+    }
+    assert_eq!(core.read(TRISA), 0xF0);
+    let lit: Vec<bool> = (0..4).map(|pin| core.pin(Port::A, pin)).collect();
+    assert_eq!(lit, [false, true, false, false]);
+    assert_eq!(output(&core, Port::A, 4), None);
+}
+
+#[test]
+fn input_edges_set_intf_and_rbif() {
+    let mut core = P16Core::default();
+    core.drive_pin(Port::B, 0, Some(true));
+    assert_eq!(
+        core.read(INTCON) & INTF,
+        INTF,
+        "INTEDG selects rising edges"
+    );
+    core.write(INTCON, 0);
+    core.drive_pin(Port::B, 0, Some(false));
+    assert_eq!(core.read(INTCON) & INTF, 0);
+
+    // Output pins do not take part in the change interrupt.
+    core.write(TRISB, 0x7F);
+    core.drive_pin(Port::B, 7, Some(true));
+    assert_eq!(core.read(INTCON) & RBIF, 0);
+    core.drive_pin(Port::B, 5, Some(true));
+    assert_eq!(core.read(INTCON) & RBIF, RBIF);
+
+    // Turning on the pull-ups raises the undriven RB6 input.
+    core.write(TRISB, 0xFF);
+    core.step();
+    core.write(INTCON, 0);
+    core.write(OPTION_REG, 0x7F);
+    core.step();
+    assert_eq!(core.read(INTCON) & RBIF, RBIF);
+}
+
+#[test]
+fn resets_turn_every_pin_into_an_input() {
+    let mut core = P16Core::default();
+    core.write(TRISB, 0x00);
+    core.write(PORTB, 0x3C);
+
+    core.reset(ResetKind::Mclr);
+    assert_eq!(core.read(TRISB), 0xFF);
+    core.write(TRISB, 0x00);
+    assert_eq!(core.read(PORTB), 0x3C, "latches survive MCLR");
+
+    core.reset(ResetKind::PowerOn);
+    core.write(TRISB, 0x00);
+    assert_eq!(core.read(PORTB), 0x00);
+}
+
+#[test]
+#[should_panic(expected = "pin 8 out of range")]
+fn drive_pin_rejects_pins_past_7() {
+    P16Core::default().drive_pin(Port::C, 8, Some(true));
+}
+
+#[test]
+#[should_panic(expected = "pin 8 out of range")]
+fn output_rejects_pins_past_7() {
+    output(&P16Core::default(), Port::A, 8);
+}
+
+#[test]
+#[should_panic(expected = "pin 8 out of range")]
+fn pin_rejects_pins_past_7() {
+    P16Core::default().pin(Port::B, 8);
+}
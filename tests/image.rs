@@ -0,0 +1,317 @@
+use p16core_sim::{
+    Image, InvalidOpcodePolicy, LoadError, P16Core, ResetKind, UnimplementedAccessPolicy,
+    peripheral::{Context, Peripheral},
+    regs::Oscillator,
+    uart::Uart,
+};
+
+const EOF: &str = ":00000001FF";
+
+/// Formats a record with a correct checksum.
+fn record(addr: u16, rtype: u8, data: &[u8]) -> String {
+    let mut bytes = vec![data.len() as u8];
+    bytes.extend(addr.to_be_bytes());
+    bytes.push(rtype);
+    bytes.extend(data);
+    let sum = bytes.iter().fold(0u8, |acc, b| acc.wrapping_add(*b));
+    bytes.push(sum.wrapping_neg());
+    format!(":{}", hex::encode_upper(bytes))
+}
+
+fn load(lines: &[&str]) -> Result<Image, LoadError> {
+    Image::from_hex_str(&lines.join("\n"))
+}
+
+#[test]
+fn loads_program_words() {
+    let image = load(&[&record(0x0002, 0x00, &[0x34, 0x12, 0xFF, 0x3F]), EOF]).unwrap();
+    assert_eq!(image.program[..3], [0x0000, 0x1234, 0x3FFF]);
+}
+
+#[test]
+fn malformed_records_report_their_line() {
+    let good = record(0x0000, 0x00, &[0x00, 0x28]);
+
+    let err = load(&[&good, ":0200000000G8D6", EOF]).unwrap_err();
+    assert!(matches!(err, LoadError::InvalidHex { line: 2 }), "{err}");
+
+    let err = load(&[&good, ":00000001", EOF]).unwrap_err();
+    assert!(matches!(err, LoadError::ShortRecord { line: 2 }), "{err}");
+
+    let mut bad_sum = good.clone();
+    bad_sum.replace_range(bad_sum.len() - 2.., "00");
+    let err = load(&[&bad_sum, EOF]).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            LoadError::Checksum {
+                line: 1,
+                expected: 0xD6,
+                found: 0x00
+            }
+        ),
+        "{err}"
+    );
+}
+
+#[test]
+fn byte_count_must_match_the_data() {
+    // Claims two data bytes but holds three, then claims four but holds two.
+    let too_long = ":0200000000280016";
+    let too_short = ":040000000028D4";
+
+    let err = load(&[too_long, EOF]).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            LoadError::LengthMismatch {
+                line: 1,
+                count: 2,
+                found: 3
+            }
+        ),
+        "{err}"
+    );
+    let err = load(&[too_short, EOF]).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            LoadError::LengthMismatch {
+                line: 1,
+                count: 4,
+                found: 2
+            }
+        ),
+        "{err}"
+    );
+}
+
+#[test]
+fn truncated_files_are_rejected() {
+    let err = load(&[&record(0x0000, 0x00, &[0x00, 0x28])]).unwrap_err();
+    assert!(matches!(err, LoadError::MissingEof), "{err}");
+}
+
+#[test]
+fn unknown_records_and_addresses_are_rejected() {
+    let err = load(&[&record(0x0000, 0x02, &[0x10, 0x00]), EOF]).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            LoadError::UnsupportedRecord {
+                line: 1,
+                rtype: 0x02
+            }
+        ),
+        "{err}"
+    );
+
+    // Word 0x1000 is just past program memory.
+    let err = load(&[&record(0x2000, 0x00, &[0x00, 0x00]), EOF]).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            LoadError::AddressOutOfRange {
+                line: 1,
+                address: 0x1000
+            }
+        ),
+        "{err}"
+    );
+}
+
+#[test]
+fn raw_binaries_hold_little_endian_words() {
+    let image = Image::from_binary(&[0x34, 0x12, 0xFF, 0xFF]).unwrap();
+    assert_eq!(image.program[..3], [0x1234, 0x3FFF, 0x0000]);
+
+    let err = Image::from_binary(&[0x00, 0x28, 0x00]).unwrap_err();
+    assert!(matches!(err, LoadError::OddLength { len: 3 }), "{err}");
+
+    assert!(Image::from_binary(&[0; 2 * 4096]).is_ok());
+    let err = Image::from_binary(&[0; 2 * 4097]).unwrap_err();
+    assert!(matches!(err, LoadError::TooLarge { words: 4097 }), "{err}");
+}
+
+#[test]
+fn hex_loads_from_readers() {
+    let hex = format!("{}\n{EOF}\n", record(0x0000, 0x00, &[0x05, 0x30]));
+    let image = Image::from_hex_reader(hex.as_bytes()).unwrap();
+    assert_eq!(image.program[0], 0x3005);
+}
+
+#[test]
+fn format_is_detected_from_content() {
+    let hex = format!("\r\n  {}\n{EOF}\n", record(0x0000, 0x00, &[0x05, 0x30]));
+    assert_eq!(
+        Image::from_bytes(hex.as_bytes()).unwrap().program[0],
+        0x3005
+    );
+    assert_eq!(Image::from_bytes(&[0x05, 0x30]).unwrap().program[0], 0x3005);
+
+    let dir = std::env::temp_dir().join(format!("p16core-image-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (hex_path, bin_path) = (dir.join("a.hex"), dir.join("a.bin"));
+    std::fs::write(&hex_path, &hex).unwrap();
+    std::fs::write(&bin_path, [0x05, 0x30]).unwrap();
+    assert_eq!(Image::from_file(&hex_path).unwrap().program[0], 0x3005);
+    assert_eq!(Image::from_file(&bin_path).unwrap().program[0], 0x3005);
+    let missing = Image::from_file(dir.join("missing.hex")).unwrap_err();
+    assert!(matches!(missing, LoadError::Io(_)), "{missing}");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn load_image_replaces_the_program_and_resets() {
+    let mut core = P16Core::from_image(Image::from_words(&[0x3005, 0x0000]).unwrap());
+    core.step();
+    assert_eq!(core.pc, 1);
+
+    core.load_image(Image::from_words(&[0x3007]).unwrap());
+    assert_eq!(core.pc, 0);
+    assert_eq!(core.cycles(), 0);
+    assert_eq!(core.program()[..2], [0x3007, 0x0000]);
+}
+
+/// A byte of scratch at 0x15, which no built-in peripheral decodes.
+#[derive(Debug, Clone, Default)]
+struct Scratch(u8);
+
+impl Peripheral for Scratch {
+    fn decodes(&self, address: u16) -> bool {
+        address == 0x15
+    }
+
+    fn read(&mut self, _address: u16, _cx: &mut Context) -> u8 {
+        self.0
+    }
+
+    fn write(&mut self, _address: u16, value: u8, _cx: &mut Context) {
+        self.0 = value;
+    }
+}
+
+#[test]
+fn load_image_keeps_peripherals_hosts_and_settings() {
+    let mut core = P16Core::default();
+    core.bus_mut().attach(Scratch::default());
+    core.bus_mut().get_mut::<Uart>().unwrap().baud_rate = 19_200;
+    let host = core.bus().get::<Uart>().unwrap().host();
+    core.invalid_opcode_policy = InvalidOpcodePolicy::Nop;
+    core.unimplemented_access_policy = UnimplementedAccessPolicy::Stop;
+    core.wdt_override = Some(false);
+    core.set_clock_hz(4_000_000);
+    core.stack.set_diagnostics(true);
+
+    core.load_image(Image::from_words(&[0x3007]).unwrap());
+    assert!(core.bus().get::<Scratch>().is_some());
+    core.write(0x15, 0x42);
+    assert_eq!(core.read(0x15), 0x42);
+    host.write(b"x");
+    assert_eq!(core.bus().get::<Uart>().unwrap().host().pending(), 1);
+    assert_eq!(core.bus().get::<Uart>().unwrap().baud_rate, 19_200);
+    assert_eq!(core.invalid_opcode_policy, InvalidOpcodePolicy::Nop);
+    assert_eq!(
+        core.unimplemented_access_policy,
+        UnimplementedAccessPolicy::Stop
+    );
+    assert_eq!(core.wdt_override, Some(false));
+    assert_eq!(core.clock_hz(), 4_000_000);
+    assert!(core.stack.diagnostics());
+}
+
+/// An MPASM-style image with program, ID locations, config word and EEPROM.
+fn full_image(config: u16) -> String {
+    [
+        record(0x0000, 0x00, &[0x05, 0x30, 0x86, 0x00]),
+        record(
+            0x4000,
+            0x00,
+            &[0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04, 0x00],
+        ),
+        record(0x400E, 0x00, &config.to_le_bytes()),
+        record(0x4200, 0x00, &[0x48, 0x00, 0x69, 0x00]),
+        EOF.to_string(),
+    ]
+    .join("\n")
+}
+
+#[test]
+fn config_ids_and_eeprom_load_from_their_regions() {
+    let image = Image::from_hex_str(&full_image(0x2F72)).unwrap();
+    assert_eq!(image.program[..2], [0x3005, 0x0086]);
+    assert_eq!(image.id_locations, [1, 2, 3, 4]);
+    assert_eq!(image.config, 0x2F72);
+    assert_eq!(image.eeprom[..3], [0x48, 0x69, 0xFF]);
+
+    let mut core = P16Core::from_image(image);
+    let config = core.config();
+    assert_eq!(config.value(), 0x2F72, "unimplemented bits are kept");
+    assert_eq!(config.oscillator(), Oscillator::Hs);
+    assert!(!config.watchdog_enabled());
+    assert!(!config.code_protected());
+    assert!(!config.data_protected());
+    assert!(!core.watchdog_enabled());
+
+    core.eeprom_mut()[0] = 0x00;
+    core.reset(ResetKind::PowerOn);
+    assert_eq!(core.eeprom()[..2], [0x00, 0x69], "EEPROM is non-volatile");
+}
+
+#[test]
+fn code_protection_hides_memory_from_read_back() {
+    let open = P16Core::from_image(Image::from_hex_str(&full_image(0x3FFF)).unwrap());
+    assert!(open.watchdog_enabled());
+    assert_eq!(open.read_back().program[0], 0x3005);
+    assert_eq!(open.read_back().eeprom[0], 0x48);
+
+    // CP and CPD are active low.
+    let protected = P16Core::from_image(Image::from_hex_str(&full_image(0x1EFF)).unwrap());
+    assert!(protected.config().code_protected());
+    assert!(protected.config().data_protected());
+    let read_back = protected.read_back();
+    assert!(read_back.program.iter().all(|&word| word == 0));
+    assert!(read_back.eeprom.iter().all(|&byte| byte == 0));
+    assert_eq!(read_back.id_locations, [1, 2, 3, 4]);
+    assert_eq!(read_back.config, 0x1EFF);
+
+    // The simulator's own view is unaffected.
+    assert_eq!(protected.image().program[0], 0x3005);
+}
+
+#[test]
+fn hex_output_round_trips() {
+    let original = Image::from_hex_str(&full_image(0x2F72)).unwrap();
+    let hex = original.to_hex();
+    let reloaded = Image::from_hex_str(&hex).unwrap();
+    assert_eq!(reloaded.program, original.program);
+    assert_eq!(reloaded.id_locations, original.id_locations);
+    assert_eq!(reloaded.config, 0x2F72);
+    assert_eq!(reloaded.eeprom, original.eeprom);
+
+    // Through a core, as when saving patched firmware.
+    let core = P16Core::from_image(original);
+    let mut out = Vec::new();
+    core.image().write_hex(&mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), hex);
+
+    let toolchain = Image::from_hex_file("test/src.X.production.hex").unwrap();
+    let reloaded = Image::from_hex_str(&toolchain.to_hex()).unwrap();
+    assert_eq!(reloaded.program, toolchain.program);
+    assert_eq!(reloaded.config, toolchain.config);
+}
+
+#[test]
+fn hex_records_carry_correct_checksums() {
+    let mut image = Image::from_words(&[0x3005]).unwrap();
+    image.config = 0x2F72;
+    image.eeprom[1] = 0x5A;
+    let expected = [
+        ":020000040000FA",
+        ":1000000005300000000000000000000000000000BB",
+        ":02400E00722F0F",
+        ":10420000FF005A00FF00FF00FF00FF00FF00FF005B",
+        ":00000001FF",
+    ];
+    assert_eq!(image.to_hex().lines().collect::<Vec<_>>(), expected);
+}
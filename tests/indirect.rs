@@ -0,0 +1,115 @@
+mod common;
+
+use common::assemble_with_symbols;
+use p16core_sim::P16Core;
+
+/// Fills a 16-byte table at `start` through INDF, then sums it back through
+/// INDF into 0x70 (shared across banks). RP1:RP0 are set to `rp`, which must
+/// not affect where INDF points.
+fn table_walk(start: u16, rp: u8) -> P16Core {
+    let irp = if start & 0x100 != 0 { "bsf" } else { "bcf" };
+    let source = format!(
+        "  #include p16core.inc
+  org 0
+  {irp} STATUS,IRP
+  movlw {rp:#x} << 5
+  iorwf STATUS,f
+  movlw {fsr:#x}
+  movwf FSR
+  movlw 0x10
+  movwf 0x71
+fill
+  movf FSR,w
+  movwf INDF
+  incf FSR,f
+  decfsz 0x71,f
+  goto fill
+
+  movlw {fsr:#x}
+  movwf FSR
+  movlw 0x10
+  movwf 0x71
+  clrf 0x70
+sum
+  movf INDF,w
+  addwf 0x70,f
+  incf FSR,f
+  decfsz 0x71,f
+  goto sum
+done
+  goto done
+  end
+",
+        fsr = start & 0xFF,
+    );
+    let (mut core, assembly) = assemble_with_symbols(&source);
+    let done = assembly.symbols["done"] as u16;
+    while core.pc != done {
+        core.step();
+        assert!(core.cycles() < 10_000, "table walk did not finish");
+    }
+    core
+}
+
+#[test]
+fn table_walks_reach_every_bank() {
+    for start in [0x020, 0x0A0, 0x120, 0x1A0] {
+        for rp in 0..4 {
+            let mut core = table_walk(start, rp);
+
+            let expected: u8 = (0..16u16)
+                .map(|i| ((start + i) & 0xFF) as u8)
+                .fold(0, u8::wrapping_add);
+            assert_eq!(
+                core.read(0x70),
+                expected,
+                "sum for table at {start:#05x}, RP={rp}"
+            );
+
+            for i in 0..16 {
+                let address = start + i;
+                core.status.rp1 = address & 0x100 != 0;
+                core.status.rp0 = address & 0x080 != 0;
+                assert_eq!(
+                    core.read(address & 0x7F),
+                    (address & 0xFF) as u8,
+                    "table entry {address:#05x} with RP={rp}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn irp_selects_the_bank_pair_regardless_of_rp() {
+    let mut core = P16Core::default();
+    core.write(0x1A5, 0x5A);
+    core.write(0x0A5, 0x11);
+
+    core.status.irp = true;
+    core.write(0x04, 0xA5);
+    assert_eq!(core.read(0x00), 0x5A);
+
+    core.status.rp0 = true;
+    core.status.rp1 = true;
+    core.status.irp = false;
+    assert_eq!(core.read(0x00), 0x11);
+
+    core.write(0x00, 0x22);
+    assert_eq!(core.read(0xA5), 0x22);
+    assert_eq!(core.read(0x1A5), 0x5A);
+}
+
+#[test]
+fn indf_through_indf_reads_zero_and_ignores_writes() {
+    for (irp, fsr) in [(false, 0x00), (false, 0x80), (true, 0x00), (true, 0x80)] {
+        let mut core = P16Core::default();
+        core.status.irp = irp;
+        core.write(0x04, fsr);
+
+        assert_eq!(core.read(0x00), 0, "IRP={irp} FSR={fsr:#04x}");
+        core.write(0x00, 0xFF);
+        assert_eq!(core.read(0x04), fsr);
+        assert_eq!(core.read(0x00), 0);
+    }
+}
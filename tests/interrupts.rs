@@ -0,0 +1,147 @@
+mod common;
+
+use common::assemble_core;
+use p16core_sim::{P16Core, Port};
+
+const TMR0: u16 = 0x01;
+const INTCON: u16 = 0x0B;
+const PIR1: u16 = 0x0C;
+const T1CON: u16 = 0x10;
+const T1L: u16 = 0x11;
+const T1H: u16 = 0x12;
+const OPTION_REG: u16 = 0x81;
+const PIE1: u16 = 0x8C;
+
+const GIE: u8 = 1 << 7;
+const PEIE: u8 = 1 << 6;
+const T0IE: u8 = 1 << 5;
+const INTE: u8 = 1 << 4;
+const RBIE: u8 = 1 << 3;
+const T0IF: u8 = 1 << 2;
+const INTF: u8 = 1 << 1;
+const RBIF: u8 = 1 << 0;
+const TMR1IF: u8 = 1 << 0;
+
+/// Spins at 0x000 and 0x001; the handler at 0x004 returns at once.
+fn core() -> P16Core {
+    let source = "  #include p16core.inc
+  org 0
+  nop
+  goto 0
+  org 4
+  retfie
+  end
+";
+    assemble_core(source)
+}
+
+/// Whether the next instruction ends in the handler.
+fn vectors(core: &mut P16Core) -> bool {
+    core.step();
+    core.pc == 0x004
+}
+
+#[test]
+fn gie_gates_every_source() {
+    for (enable, flag) in [(T0IE, T0IF), (INTE, INTF), (RBIE, RBIF)] {
+        let mut core = core();
+        core.write(INTCON, enable | flag);
+        assert!(!core.interrupt_pending());
+        assert!(!vectors(&mut core), "INTCON 0x{:02X}", enable | flag);
+
+        core.write(INTCON, flag);
+        core.intcon.gie = true;
+        assert!(!vectors(&mut core), "flag 0x{flag:02X} is not enabled");
+
+        core.write(INTCON, GIE | enable | flag);
+        assert!(core.interrupt_pending());
+        assert!(vectors(&mut core), "INTCON 0x{:02X}", GIE | enable | flag);
+    }
+}
+
+#[test]
+fn peie_gates_peripheral_sources() {
+    let mut core = core();
+    core.write(PIE1, TMR1IF);
+    core.write(PIR1, TMR1IF);
+    core.write(INTCON, GIE);
+    assert!(!vectors(&mut core));
+
+    core.write(INTCON, PEIE);
+    assert!(!vectors(&mut core), "GIE also gates peripherals");
+
+    core.write(INTCON, GIE | PEIE);
+    assert!(vectors(&mut core));
+}
+
+#[test]
+fn entry_clears_gie_and_retfie_sets_it() {
+    let mut core = core();
+    core.stack.set_diagnostics(true);
+    core.step();
+    core.write(INTCON, GIE | INTE | INTF);
+    assert!(vectors(&mut core));
+    assert_eq!(core.read(INTCON), INTE | INTF, "GIE is cleared on entry");
+    assert_eq!(core.stack.depth(), 1);
+
+    // With GIE clear the still-set flag cannot re-enter the handler.
+    assert!(!core.interrupt_pending());
+
+    core.write(INTCON, INTE);
+    assert_eq!(core.step(), 2);
+    assert_eq!(core.pc, 0x000, "back to the goto's target");
+    assert_eq!(core.read(INTCON), GIE | INTE);
+    assert_eq!(core.stack.depth(), 0);
+
+    // A flag the handler leaves set interrupts again straight after RETFIE.
+    core.write(INTCON, GIE | INTE | INTF);
+    assert!(vectors(&mut core));
+    assert_eq!(core.step(), 2 + 2);
+    assert_eq!(core.pc, 0x004);
+    assert!(!core.intcon.gie);
+    assert_eq!(core.stack.depth(), 1);
+}
+
+#[test]
+fn tmr0_overflow_raises_t0if() {
+    let mut core = core();
+    // Timer mode, prescaler on the watchdog: TMR0 counts every cycle.
+    core.write(OPTION_REG, 0x08);
+    core.write(TMR0, 0xFF);
+    core.write(INTCON, GIE | T0IE);
+    assert!(vectors(&mut core));
+    assert_eq!(core.read(INTCON) & T0IF, T0IF);
+}
+
+#[test]
+fn rb0_edge_raises_intf() {
+    let mut core = core();
+    core.write(INTCON, GIE | INTE);
+    assert!(!vectors(&mut core));
+    core.drive_pin(Port::B, 0, Some(true));
+    assert!(vectors(&mut core));
+    assert_eq!(core.read(INTCON) & INTF, INTF);
+}
+
+#[test]
+fn portb_change_raises_rbif() {
+    let mut core = core();
+    core.write(INTCON, GIE | RBIE);
+    core.drive_pin(Port::B, 3, Some(true));
+    assert!(!vectors(&mut core), "RB3 is not a change pin");
+    core.drive_pin(Port::B, 4, Some(true));
+    assert!(vectors(&mut core));
+    assert_eq!(core.read(INTCON) & RBIF, RBIF);
+}
+
+#[test]
+fn tmr1_overflow_raises_a_peripheral_interrupt() {
+    let mut core = core();
+    core.write(T1L, 0xFF);
+    core.write(T1H, 0xFF);
+    core.write(T1CON, 0x01);
+    core.write(PIE1, TMR1IF);
+    core.write(INTCON, GIE | PEIE);
+    assert!(vectors(&mut core));
+    assert_eq!(core.read(PIR1) & TMR1IF, TMR1IF);
+}
@@ -0,0 +1,120 @@
+use p16core_sim::{
+    P16Core, ResetKind,
+    peripheral::{Context, Irq, Peripheral},
+    tmr0::Timer0,
+};
+
+const TMR0: u16 = 0x01;
+const PIR1: u16 = 0x0C;
+/// Unused in every built-in peripheral.
+const COUNT: u16 = 0x15;
+
+const TMR2IF: u8 = 1 << 1;
+
+/// Counts instruction cycles at 0x15 and raises TMR2IF every `period`
+/// cycles. Optionally also claims TMR0's address.
+#[derive(Debug, Clone)]
+struct Counter {
+    count: u8,
+    period: u8,
+    shadow_tmr0: bool,
+    resets: Vec<ResetKind>,
+}
+
+impl Counter {
+    fn new(period: u8) -> Self {
+        Self {
+            count: 0,
+            period,
+            shadow_tmr0: false,
+            resets: Vec::new(),
+        }
+    }
+}
+
+impl Peripheral for Counter {
+    fn decodes(&self, address: u16) -> bool {
+        address == COUNT || (self.shadow_tmr0 && address == TMR0)
+    }
+
+    fn read(&mut self, address: u16, _cx: &mut Context) -> u8 {
+        if address == TMR0 { 0xA5 } else { self.count }
+    }
+
+    fn write(&mut self, _address: u16, value: u8, _cx: &mut Context) {
+        self.count = value;
+    }
+
+    fn tick(&mut self, cx: &mut Context) {
+        self.count = self.count.wrapping_add(1);
+        if self.count.is_multiple_of(self.period) {
+            cx.raise(Irq::Tmr2);
+        }
+    }
+
+    fn reset(&mut self, kind: ResetKind) {
+        self.count = 0;
+        self.resets.push(kind);
+    }
+}
+
+#[test]
+fn attached_peripherals_are_ticked_and_raise_interrupts() {
+    let mut core = P16Core::default();
+    core.bus_mut().attach(Counter::new(5));
+
+    core.write(COUNT, 0);
+    for _ in 0..4 {
+        core.step();
+    }
+    assert_eq!(core.read(COUNT), 4);
+    assert_eq!(core.read(PIR1) & TMR2IF, 0);
+    core.step();
+    assert_eq!(core.read(PIR1) & TMR2IF, TMR2IF);
+
+    // The flag stays set until firmware clears it.
+    core.write(PIR1, 0);
+    core.step();
+    assert_eq!(core.read(PIR1) & TMR2IF, 0);
+
+    core.reset(ResetKind::Mclr);
+    let counter = core.bus().get::<Counter>().unwrap();
+    assert_eq!(counter.count, 0);
+    assert_eq!(counter.resets, [ResetKind::Mclr]);
+}
+
+#[test]
+fn last_attached_peripheral_decodes_shared_addresses() {
+    let mut core = P16Core::default();
+    core.write(TMR0, 0x12);
+    assert_eq!(core.read(TMR0), 0x12);
+
+    let mut shadow = Counter::new(200);
+    shadow.shadow_tmr0 = true;
+    core.bus_mut().attach(shadow);
+    assert_eq!(core.read(TMR0), 0xA5);
+    core.write(TMR0, 0x00);
+    assert_eq!(core.bus().get::<Timer0>().unwrap().value, 0x12);
+
+    // A second counter takes over 0x15 from the first.
+    core.bus_mut().attach(Counter::new(200));
+    core.write(COUNT, 0x40);
+    assert_eq!(core.read(COUNT), 0x40);
+    assert_eq!(core.read(TMR0), 0xA5, "the shadow still owns TMR0");
+}
+
+#[test]
+fn get_and_get_mut_downcast_to_the_latest_of_a_type() {
+    let mut core = P16Core::default();
+    assert!(core.bus().get::<Counter>().is_none());
+
+    core.bus_mut().attach(Counter::new(3));
+    core.bus_mut().attach(Counter::new(7));
+    assert_eq!(core.bus().get::<Counter>().unwrap().period, 7);
+
+    core.bus_mut().get_mut::<Counter>().unwrap().count = 0x33;
+    assert_eq!(core.read(COUNT), 0x33);
+
+    core.bus_mut().get_mut::<Timer0>().unwrap().value = 0x44;
+    assert_eq!(core.read(TMR0), 0x44);
+}
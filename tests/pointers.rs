@@ -0,0 +1,114 @@
+mod common;
+
+use common::assemble_with_symbols;
+use p16core_sim::P16Core;
+
+const INDF1: u16 = 0x0E;
+const INDF2: u16 = 0x0F;
+const PTR1L: u16 = 0x1C;
+const PTR1H: u16 = 0x1D;
+const PTR2L: u16 = 0x1E;
+const PTR2H: u16 = 0x1F;
+
+fn set_pointers(core: &mut P16Core, ptr1: u16, ptr2: u16) {
+    core.write(PTR1L, ptr1 as u8);
+    core.write(PTR1H, (ptr1 >> 8) as u8);
+    core.write(PTR2L, ptr2 as u8);
+    core.write(PTR2H, (ptr2 >> 8) as u8);
+}
+
+#[test]
+fn pointers_reach_data_memory_in_every_bank() {
+    let mut core = P16Core::default();
+    for address in [0x020, 0x0A0, 0x120, 0x1A0, 0x070] {
+        set_pointers(&mut core, address, address);
+        core.write(INDF1, address as u8 ^ 0x5A);
+        assert_eq!(core.read(INDF2), address as u8 ^ 0x5A, "{address:#05x}");
+    }
+    // INDF1/INDF2 are mirrored in every bank and ignore RP1:RP0.
+    set_pointers(&mut core, 0x020, 0x1A0);
+    core.status.rp0 = true;
+    assert_eq!(core.read(INDF1), 0x020 ^ 0x5A);
+    assert_eq!(core.read(INDF2), 0xA0 ^ 0x5A);
+}
+
+#[test]
+fn indirect_ports_through_pointers_read_zero() {
+    let mut core = P16Core::default();
+    set_pointers(&mut core, INDF2, 0x00);
+    core.write(INDF1, 0xFF);
+    assert_eq!(core.read(INDF1), 0);
+    assert_eq!(core.read(INDF2), 0);
+}
+
+#[test]
+fn table_lookup_from_program_memory() {
+    let source = "  #include p16core.inc
+  org 0
+  movlw high (table * 2) | 0x80
+  movwf PTR1H
+  movlw low (table * 2)
+  movwf PTR1L
+  movlw 0x20
+  movwf PTR2L
+  clrf PTR2H
+  movlw 8
+  movwf 0x70
+copy
+  movf INDF1,w
+  movwf INDF2
+  decfsz 0x70,f
+  goto copy
+done
+  goto done
+
+  org 0x123
+table
+  dw 0x3F11, 0x0022, 0x1233, 0x0044
+  end
+";
+    let (mut core, assembly) = assemble_with_symbols(source);
+    let done = assembly.symbols["done"] as u16;
+    core.pointer_post_increment = true;
+
+    while core.pc != done {
+        core.step();
+        assert!(core.cycles() < 1_000, "copy loop did not finish");
+    }
+
+    // Each word comes out low byte first.
+    let copied: Vec<u8> = (0x20..0x28).map(|address| core.read(address)).collect();
+    assert_eq!(copied, [0x11, 0x3F, 0x22, 0x00, 0x33, 0x12, 0x44, 0x00]);
+    assert_eq!(core.read(PTR1L), 0x4E);
+    assert_eq!(core.read(PTR1H), 0x82);
+    assert_eq!(core.read(PTR2L), 0x28);
+}
+
+#[test]
+fn pointers_hold_still_without_post_increment() {
+    let mut core = P16Core::default();
+    set_pointers(&mut core, 0x030, 0x031);
+    core.write(INDF1, 1);
+    core.write(INDF1, 2);
+    core.read(INDF2);
+    assert_eq!(core.read(0x30), 2);
+    assert_eq!(core.read(PTR1L), 0x30);
+    assert_eq!(core.read(PTR2L), 0x31);
+}
+
+#[test]
+fn program_memory_is_read_only_and_ends_at_0x9fff() {
+    let mut core = P16Core::default();
+    core.program_mut()[0x000] = 0x2ABC;
+    core.program_mut()[0xFFF] = 0x3FFF;
+    set_pointers(&mut core, 0x8001, 0x9FFE);
+    assert_eq!(core.read(INDF1), 0x2A);
+    assert_eq!(core.read(INDF2), 0xFF);
+
+    core.write(INDF1, 0x00);
+    assert_eq!(core.program()[0], 0x2ABC);
+
+    set_pointers(&mut core, 0x9FFF, 0xA000);
+    assert_eq!(core.read(INDF1), 0x3F);
+    assert_eq!(core.read(INDF2), 0x00);
+}
@@ -0,0 +1,149 @@
+use p16core_sim::{P16Core, ResetKind};
+
+const TMR0: u16 = 0x01;
+const STATUS: u16 = 0x03;
+const FSR: u16 = 0x04;
+const PCLATH: u16 = 0x0A;
+const INTCON: u16 = 0x0B;
+const PIR1: u16 = 0x0C;
+const OPTION_REG: u16 = 0x81;
+const TRISB: u16 = 0x86;
+const PIE1: u16 = 0x8C;
+
+/// A core with every register moved away from its reset value. TO and PD
+/// are both clear, so resets that keep them are told apart from ones that
+/// set them.
+fn dirty() -> P16Core {
+    let mut core = P16Core::default();
+    core.write(0x20, 0x77);
+    core.write(TMR0, 0x33);
+    core.write(FSR, 0x44);
+    core.write(PCLATH, 0x18);
+    core.write(INTCON, 0x7F);
+    core.write(PIR1, 0x01);
+    core.write(OPTION_REG, 0x00);
+    core.write(TRISB, 0x00);
+    core.write(PIE1, 0x01);
+    core.w = 0x5A;
+    core.status.set(0xE7);
+    core.pc = 0x123;
+    core
+}
+
+/// Register values after a reset, in the datasheet's reset table order.
+#[derive(Debug, PartialEq)]
+struct Registers {
+    w: u8,
+    tmr0: u8,
+    pc: u16,
+    status: u8,
+    fsr: u8,
+    pclath: u8,
+    intcon: u8,
+    pir1: u8,
+    option: u8,
+    trisb: u8,
+    pie1: u8,
+    ram: u8,
+}
+
+fn registers(core: &mut P16Core) -> Registers {
+    Registers {
+        w: core.w,
+        tmr0: core.read(TMR0),
+        pc: core.pc,
+        status: core.read(STATUS),
+        fsr: core.read(FSR),
+        pclath: core.read(PCLATH),
+        intcon: core.read(INTCON),
+        pir1: core.read(PIR1),
+        option: core.read(OPTION_REG),
+        trisb: core.read(TRISB),
+        pie1: core.read(PIE1),
+        ram: core.read(0x20),
+    }
+}
+
+/// The "MCLR reset / WDT reset" column: the reset vector and control
+/// registers, with W, FSR, TMR0, RBIF, RAM and the STATUS flags unchanged.
+fn warm(status: u8) -> Registers {
+    Registers {
+        w: 0x5A,
+        tmr0: 0x33,
+        pc: 0x000,
+        status,
+        fsr: 0x44,
+        pclath: 0x00,
+        intcon: 0x01,
+        pir1: 0x00,
+        option: 0xFF,
+        trisb: 0xFF,
+        pie1: 0x00,
+        ram: 0x77,
+    }
+}
+
+#[test]
+fn power_on_and_brown_out_clear_everything() {
+    for kind in [ResetKind::PowerOn, ResetKind::BrownOut] {
+        let mut core = dirty();
+        core.reset(kind);
+        let expected = Registers {
+            w: 0x00,
+            tmr0: 0x00,
+            pc: 0x000,
+            status: 0x18,
+            fsr: 0x00,
+            pclath: 0x00,
+            intcon: 0x00,
+            pir1: 0x00,
+            option: 0xFF,
+            trisb: 0xFF,
+            pie1: 0x00,
+            ram: 0x00,
+        };
+        assert_eq!(registers(&mut core), expected, "{kind:?}");
+    }
+}
+
+#[test]
+fn mclr_keeps_to_and_pd() {
+    // 000q quuu
+    let mut core = dirty();
+    core.reset(ResetKind::Mclr);
+    assert_eq!(registers(&mut core), warm(0x07));
+
+    let mut core = dirty();
+    core.status.to = true;
+    core.reset(ResetKind::Mclr);
+    assert_eq!(registers(&mut core), warm(0x17));
+}
+
+#[test]
+fn mclr_during_sleep_sets_to_and_clears_pd() {
+    // 0001 0uuu
+    let mut core = dirty();
+    core.reset(ResetKind::MclrSleep);
+    assert_eq!(registers(&mut core), warm(0x17));
+}
+
+#[test]
+fn watchdog_reset_clears_to_and_sets_pd() {
+    // 0000 1uuu
+    let mut core = dirty();
+    core.reset(ResetKind::Watchdog);
+    assert_eq!(registers(&mut core), warm(0x0F));
+}
+
+#[test]
+fn watchdog_wake_only_clears_to_and_pd() {
+    // uuu0 0uuu, execution continues at PC.
+    let mut core = dirty();
+    core.status.to = true;
+    core.status.pd = true;
+    let mut expected = registers(&mut core);
+    expected.status = 0xE7;
+
+    core.reset(ResetKind::WatchdogWake);
+    assert_eq!(registers(&mut core), expected);
+}
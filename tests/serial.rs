@@ -0,0 +1,58 @@
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::{Duration, Instant},
+};
+
+use p16core_sim::{
+    P16Core,
+    serial::{Backend, Connection},
+    uart::Uart,
+};
+
+#[test]
+fn backends_parse_from_the_command_line() {
+    assert_eq!("stdio".parse(), Ok(Backend::Stdio));
+    assert_eq!("pty".parse(), Ok(Backend::Pty));
+    assert_eq!(
+        "tcp:4000".parse(),
+        Ok(Backend::Tcp("127.0.0.1:4000".to_string()))
+    );
+    assert_eq!(
+        "tcp:0.0.0.0:23".parse(),
+        Ok(Backend::Tcp("0.0.0.0:23".to_string()))
+    );
+    assert!("tcp:".parse::<Backend>().is_err());
+    assert!("serial".parse::<Backend>().is_err());
+}
+
+#[test]
+fn tcp_client_talks_to_the_uart() {
+    let mut core = P16Core::default();
+    let host = core.bus().get::<Uart>().unwrap().host();
+    let connection = Connection::open(&"tcp:0".parse().unwrap(), host.clone()).unwrap();
+    let address = connection.description().strip_prefix("tcp ").unwrap();
+
+    let mut client = TcpStream::connect(address).unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    client.write_all(b"ping").unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while host.pending() < 4 {
+        assert!(Instant::now() < deadline, "UART never received the bytes");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    // Firmware side: enable the port and transmit.
+    core.write(0x18, 0x80);
+    core.write(0x19, b'!');
+    while core.cycles() < 6_000 {
+        core.step();
+    }
+    connection.flush();
+
+    let mut reply = [0; 1];
+    client.read_exact(&mut reply).unwrap();
+    assert_eq!(&reply, b"!");
+}
@@ -0,0 +1,145 @@
+mod common;
+
+use common::assemble_with_symbols;
+use p16core_sim::{P16Core, Port};
+
+const OPTION_REG: u16 = 0x81;
+
+/// Sleeps with INTE set and GIE as given, then counts in 0x20 and 0x21 which
+/// path execution took after waking.
+fn sleeper(gie: bool) -> (P16Core, u16) {
+    let source = format!(
+        "  #include p16core.inc
+  org 0
+  goto main
+  org 4
+  incf 0x21,f
+  bcf INTCON,INTF
+  retfie
+main
+  movlw {}
+  movwf INTCON
+  sleep
+after
+  incf 0x20,f
+done
+  goto done
+  end
+",
+        if gie { "0x90" } else { "0x10" }
+    );
+    let (mut core, assembly) = assemble_with_symbols(&source);
+    let after = assembly.symbols["after"] as u16;
+    core.wdt_override = Some(false);
+    for _ in 0..4 {
+        core.step();
+    }
+    assert!(core.sleeping());
+    assert_eq!(core.pc, after);
+    (core, after)
+}
+
+#[test]
+fn sleep_sets_to_and_clears_pd() {
+    let (mut core, after) = sleeper(false);
+    assert!(core.status.to);
+    assert!(!core.status.pd);
+
+    // Nothing happens while asleep but the cycle count.
+    let cycles = core.cycles();
+    for _ in 0..100 {
+        assert_eq!(core.step(), 1);
+    }
+    assert!(core.sleeping());
+    assert_eq!(core.pc, after);
+    assert_eq!(core.cycles(), cycles + 100);
+    assert_eq!(core.sleep_until_wake(), None, "only a pin can wake it");
+}
+
+#[test]
+fn interrupt_without_gie_wakes_to_the_next_instruction() {
+    let (mut core, after) = sleeper(false);
+    core.drive_pin(Port::B, 0, Some(true));
+    core.step();
+    assert!(!core.sleeping());
+    assert_eq!(core.pc, after);
+
+    core.step();
+    core.step();
+    assert_eq!(core.read(0x20), 1);
+    assert_eq!(core.read(0x21), 0, "the interrupt was not taken");
+    assert!(core.status.to);
+    assert!(!core.status.pd);
+}
+
+#[test]
+fn interrupt_with_gie_runs_the_next_instruction_then_vectors() {
+    let (mut core, _) = sleeper(true);
+    core.drive_pin(Port::B, 0, Some(true));
+    assert_eq!(core.sleep_until_wake(), Some(1));
+    assert!(!core.sleeping());
+
+    // The instruction after SLEEP, then the interrupt entry.
+    core.step();
+    assert_eq!(core.read(0x20), 1);
+    assert_eq!(core.pc, 0x0004);
+    for _ in 0..4 {
+        core.step();
+    }
+    assert_eq!(core.read(0x21), 1);
+}
+
+#[test]
+fn watchdog_wakes_the_core_without_resetting_it() {
+    let (mut core, after) = sleeper(false);
+    core.wdt_override = None;
+    // Prescaler on the watchdog at 1:2.
+    core.write(OPTION_REG, 0x09);
+
+    let start = core.cycles();
+    let mut stepped = 0;
+    while core.sleeping() {
+        stepped += core.step() as u64;
+    }
+    // SLEEP cleared the watchdog; 36 ms at 5 MIPS.
+    assert_eq!(stepped, 180_000);
+    assert_eq!(core.cycles(), start + stepped);
+    assert_eq!(core.pc, after, "execution continues after SLEEP");
+    assert!(!core.status.to);
+    assert!(!core.status.pd);
+    assert_eq!(core.read(OPTION_REG), 0x09, "registers are untouched");
+
+    core.step();
+    assert_eq!(core.read(0x20), 1);
+}
+
+#[test]
+fn fast_forward_matches_stepping() {
+    let (mut stepped, after) = sleeper(false);
+    stepped.wdt_override = None;
+    stepped.write(OPTION_REG, 0x09);
+    let mut skipped = stepped.clone();
+
+    while stepped.sleeping() {
+        stepped.step();
+    }
+    let start = skipped.cycles();
+    assert_eq!(skipped.sleep_until_wake(), Some(180_000));
+    assert!(!skipped.sleeping());
+    assert_eq!(skipped.cycles(), stepped.cycles());
+    assert_eq!(skipped.cycles() - start, 180_000);
+    assert_eq!(skipped.pc, stepped.pc);
+    assert_eq!(skipped.status.value(), stepped.status.value());
+    assert_eq!(skipped.sleep_until_wake(), Some(0), "already awake");
+
+    // Both sleep again; SLEEP's own cycle now counts towards the time-out.
+    skipped.pc = after - 1;
+    stepped.pc = after - 1;
+    stepped.step();
+    skipped.step();
+    assert_eq!(skipped.sleep_until_wake(), Some(179_999));
+    while stepped.sleeping() {
+        stepped.step();
+    }
+    assert_eq!(skipped.cycles(), stepped.cycles());
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use common::{assemble_core, assemble_with_symbols};
+use p16core_sim::{
+    P16Core, asm,
+    stack::{StackEvent, StackFault},
+};
+
+/// Nine nested calls, one deeper than the hardware stack: `f1` calls `f2`
+/// and so on down to `f9`, and each returns straight after its call.
+fn nested() -> (P16Core, asm::Assembly) {
+    let mut source = String::from("  org 0\n  call f1\ndone\n  goto done\n");
+    for level in 1..9 {
+        source += &format!("f{level}\n  call f{}\n  return\n", level + 1);
+    }
+    source += "f9\n  return\n  end\n";
+    assemble_with_symbols(&source)
+}
+
+#[test]
+fn ninth_call_overwrites_the_oldest_return_address() {
+    let (mut core, assembly) = nested();
+    for _ in 0..9 {
+        core.step();
+    }
+    assert_eq!(core.pc, assembly.symbols["f9"] as u16);
+
+    // The first eight returns unwind f9..f2; the ninth finds f9's return
+    // address where the one back to `done` used to be.
+    let f8_return = assembly.symbols["f8"] as u16 + 1;
+    let mut returns = Vec::new();
+    for _ in 0..9 {
+        core.step();
+        returns.push(core.pc);
+    }
+    assert_eq!(returns[0], f8_return);
+    assert_eq!(returns[7], assembly.symbols["f1"] as u16 + 1);
+    assert_eq!(returns[8], f8_return, "not {:#X}", assembly.symbols["done"]);
+}
+
+#[test]
+fn diagnostics_record_overflow_and_underflow_with_the_call_history() {
+    let (mut core, assembly) = nested();
+    core.stack.set_diagnostics(true);
+    let address = |name: &str| assembly.symbols[name] as u16;
+
+    for _ in 0..9 {
+        core.step();
+    }
+    assert_eq!(core.stack.depth(), 8);
+    let calls: Vec<u16> = [0]
+        .into_iter()
+        .chain((1..8).map(|level| address(&format!("f{level}"))))
+        .collect();
+    assert_eq!(
+        core.stack.events(),
+        [StackEvent {
+            fault: StackFault::Overflow,
+            pc: address("f8"),
+            calls: calls.clone(),
+        }]
+    );
+
+    for _ in 0..9 {
+        core.step();
+    }
+    let events = core.stack.take_events();
+    assert_eq!(events.len(), 2);
+    assert_eq!(
+        events[1],
+        StackEvent {
+            fault: StackFault::Underflow,
+            pc: address("f1") + 1,
+            calls: Vec::new(),
+        }
+    );
+    assert_eq!(
+        events[0].to_string(),
+        format!(
+            "stack overflow at 0x{:04X} (calls: {})",
+            address("f8"),
+            calls
+                .iter()
+                .map(|call| format!("0x{call:04X}"))
+                .collect::<Vec<_>>()
+                .join(" > ")
+        )
+    );
+    assert_eq!(
+        events[1].to_string(),
+        format!("stack underflow at 0x{:04X}", address("f1") + 1)
+    );
+    assert!(core.stack.events().is_empty(), "take_events clears the log");
+}
+
+#[test]
+fn return_from_an_empty_stack_is_reported() {
+    let source = "  org 0\n  nop\n  return\n  end\n";
+    let mut core = assemble_core(source);
+    core.step();
+    core.step();
+    assert!(
+        core.stack.events().is_empty(),
+        "diagnostics are off by default"
+    );
+
+    let mut core = assemble_core(source);
+    core.stack.set_diagnostics(true);
+    core.step();
+    core.step();
+    assert_eq!(core.stack.events().len(), 1);
+    assert_eq!(core.stack.events()[0].fault, StackFault::Underflow);
+    assert_eq!(core.stack.events()[0].pc, 0x001);
+}
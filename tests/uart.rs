@@ -0,0 +1,152 @@
+mod common;
+
+use common::{assemble_core, run_for};
+use p16core_sim::{P16Core, uart::Uart};
+
+const PIR1: u16 = 0x0C;
+const RCSTA: u16 = 0x18;
+const TXREG: u16 = 0x19;
+const RCREG: u16 = 0x1A;
+
+const RCIF: u8 = 1 << 5;
+const TXIF: u8 = 1 << 4;
+const SPEN_CREN: u8 = 0x90;
+const FERR: u8 = 1 << 2;
+const OERR: u8 = 1 << 1;
+
+/// Instruction cycles per frame at the default 20 MHz and 9600 baud.
+const FRAME: u64 = 5208;
+
+#[test]
+fn firmware_output_reaches_the_host_one_frame_at_a_time() {
+    let mut core = assemble_core(
+        "  #include p16core.inc
+  org 0
+  bsf RCSTA,SPEN
+  movlw 'O'
+  call putc
+  movlw 'K'
+  call putc
+done
+  goto done
+putc
+  btfss PIR1,TXIF
+  goto putc
+  movwf TXREG
+  return
+  end
+",
+    );
+    let host = core.bus().get::<Uart>().unwrap().host();
+
+    run_for(&mut core, FRAME);
+    assert_eq!(host.read(), b"");
+    run_for(&mut core, 20);
+    assert_eq!(host.read(), b"O");
+    run_for(&mut core, FRAME);
+    assert_eq!(host.read(), b"K");
+}
+
+#[test]
+fn received_bytes_raise_rcif_and_interrupt() {
+    let mut core = assemble_core(
+        "  #include p16core.inc
+  org 0
+  goto main
+  org 4
+  movf RCREG,w
+  addlw 1
+  movwf TXREG
+  retfie
+main
+  movlw (1 << SPEN) | (1 << CREN)
+  movwf RCSTA
+  bsf STATUS,RP0
+  bsf PIE1,RCIE
+  bcf STATUS,RP0
+  bsf INTCON,PEIE
+  bsf INTCON,GIE
+idle
+  goto idle
+  end
+",
+    );
+    let host = core.bus().get::<Uart>().unwrap().host();
+    host.write(b"HAL");
+
+    run_for(&mut core, 5 * FRAME);
+    assert_eq!(host.read(), b"IBM");
+    assert_eq!(host.pending(), 0);
+    assert_eq!(core.read(PIR1) & RCIF, 0);
+}
+
+#[test]
+fn txif_follows_txreg() {
+    let mut core = P16Core::default();
+    run_for(&mut core, 1);
+    assert_eq!(core.read(PIR1) & TXIF, 0, "port disabled");
+
+    core.write(RCSTA, SPEN_CREN);
+    assert_eq!(core.read(PIR1) & TXIF, TXIF);
+    core.write(TXREG, b'a');
+    assert_eq!(core.read(PIR1) & TXIF, 0);
+    run_for(&mut core, 1);
+    assert_eq!(core.read(PIR1) & TXIF, TXIF, "moved to the shift register");
+
+    // Firmware cannot clear a flag the hardware is driving.
+    core.write(PIR1, 0);
+    assert_eq!(core.read(PIR1) & TXIF, TXIF);
+}
+
+#[test]
+fn overrun_stops_reception_until_cren_is_cleared() {
+    let mut core = P16Core::default();
+    let host = core.bus().get::<Uart>().unwrap().host();
+    core.write(RCSTA, SPEN_CREN);
+    host.write(b"abcd");
+
+    run_for(&mut core, 4 * FRAME);
+    assert_eq!(core.read(RCSTA) & OERR, OERR);
+    assert_eq!(host.pending(), 1, "reception stopped after the overrun");
+    assert_eq!(core.read(RCREG), b'a');
+    assert_eq!(core.read(RCREG), b'b');
+    assert_eq!(core.read(PIR1) & RCIF, 0);
+
+    core.write(RCSTA, SPEN_CREN & !0x10);
+    assert_eq!(core.read(RCSTA) & OERR, 0);
+    core.write(RCSTA, SPEN_CREN);
+    run_for(&mut core, FRAME + 1);
+    assert_eq!(core.read(RCREG), b'd');
+}
+
+#[test]
+fn ferr_belongs_to_the_byte_at_the_head_of_the_fifo() {
+    let mut core = P16Core::default();
+    let host = core.bus().get::<Uart>().unwrap().host();
+    core.write(RCSTA, SPEN_CREN);
+    host.write_framing_error(0x00);
+    host.write(b"x");
+
+    run_for(&mut core, 2 * FRAME + 2);
+    assert_eq!(core.read(PIR1) & RCIF, RCIF);
+    assert_eq!(core.read(RCSTA) & FERR, FERR);
+    assert_eq!(core.read(RCREG), 0x00);
+    assert_eq!(core.read(RCSTA) & FERR, 0);
+    assert_eq!(core.read(RCREG), b'x');
+}
+
+#[test]
+fn frame_time_follows_clock_and_baud_rate() {
+    let mut core = P16Core::default();
+    core.set_clock_hz(4_000_000);
+    core.bus_mut().get_mut::<Uart>().unwrap().baud_rate = 115_200;
+    let host = core.bus().get::<Uart>().unwrap().host();
+    core.write(RCSTA, SPEN_CREN);
+    core.write(TXREG, b'!');
+
+    // 10 bits at 115200 baud from a 1 MHz instruction clock.
+    run_for(&mut core, 86);
+    assert_eq!(host.read(), b"");
+    run_for(&mut core, 1);
+    assert_eq!(host.read(), b"!");
+}
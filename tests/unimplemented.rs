@@ -0,0 +1,184 @@
+mod common;
+
+use common::assemble_core;
+use p16core_sim::{Halt, Instruction, P16Core, UnimplementedAccessPolicy, exec::decode};
+
+/// 0x09 is PORTE on larger parts; nothing implements it here.
+const SOURCE: &str = "  #include p16core.inc
+  org 0
+  movlw 0x3C
+  movwf 0x09
+  movf 0x09,w
+  btfss 0x09,0
+  nop
+done
+  goto done
+  end
+";
+
+fn loaded(source: &str, policy: UnimplementedAccessPolicy) -> P16Core {
+    let mut core = assemble_core(source);
+    core.unimplemented_access_policy = policy;
+    core
+}
+
+fn access(pc: u16, word: u16, write: bool) -> Halt {
+    Halt::UnimplementedAccess {
+        pc,
+        instruction: decode(word),
+        address: 0x009,
+        write,
+    }
+}
+
+#[test]
+fn read_zero_ignores_writes_silently() {
+    let mut core = loaded(SOURCE, UnimplementedAccessPolicy::ReadZero);
+    for _ in 0..4 {
+        core.step();
+    }
+    assert_eq!(core.w, 0x00);
+    assert_eq!(core.pc, 0x004, "bit 0 reads clear, so btfss did not skip");
+    assert!(core.halted().is_none());
+    assert!(core.warnings().is_empty());
+}
+
+#[test]
+fn warn_logs_every_access_and_carries_on() {
+    let mut core = loaded(SOURCE, UnimplementedAccessPolicy::Warn);
+    for _ in 0..4 {
+        core.step();
+    }
+    assert_eq!(core.w, 0x00);
+    assert!(core.halted().is_none());
+    let warnings = core.take_warnings();
+    assert_eq!(
+        warnings,
+        [
+            access(0x001, 0x0089, true),
+            access(0x002, 0x0809, false),
+            access(0x003, 0x1C09, false),
+        ]
+    );
+    assert_eq!(
+        warnings[0].to_string(),
+        "write to unimplemented address 0x009 at 0x0001 (movwf 0x09)"
+    );
+    assert!(core.warnings().is_empty(), "take_warnings clears the log");
+}
+
+#[test]
+fn stop_halts_on_the_faulting_instruction() {
+    let mut core = loaded(SOURCE, UnimplementedAccessPolicy::Stop);
+    core.step();
+    core.step();
+    assert_eq!(core.halted(), Some(&access(0x001, 0x0089, true)));
+    assert_eq!(core.pc, 0x001);
+    assert_eq!(core.step(), 0, "a halted core does not run");
+    assert!(core.warnings().is_empty());
+
+    // A skip that faults stays on the skip too.
+    core.resume();
+    core.pc = 0x003;
+    core.step();
+    assert_eq!(core.halted(), Some(&access(0x003, 0x1C09, false)));
+    assert_eq!(core.pc, 0x003);
+
+    core.unimplemented_access_policy = UnimplementedAccessPolicy::ReadZero;
+    core.resume();
+    core.step();
+    assert_eq!(core.pc, 0x004);
+}
+
+/// Reads 0x200 and writes 0xFFFF through PTR1.
+const BEYOND: &str = "  #include p16core.inc
+  org 0
+  movlw 0x02
+  movwf PTR1H
+  clrf PTR1L
+  movf INDF1,w
+  movlw 0xFF
+  movwf PTR1H
+  movwf PTR1L
+  movwf INDF1
+done
+  goto done
+  end
+";
+
+#[test]
+fn addresses_beyond_the_banks_follow_the_policy() {
+    let mut core = loaded(BEYOND, UnimplementedAccessPolicy::ReadZero);
+    for _ in 0..4 {
+        core.step();
+    }
+    assert_eq!(core.w, 0x00);
+    assert!(core.status.z);
+    for _ in 0..4 {
+        core.step();
+    }
+    assert!(core.halted().is_none());
+    assert!(core.warnings().is_empty());
+
+    let mut core = loaded(BEYOND, UnimplementedAccessPolicy::Warn);
+    for _ in 0..8 {
+        core.step();
+    }
+    let accesses: Vec<(u16, u16, bool)> = core
+        .warnings()
+        .iter()
+        .map(|warning| match warning {
+            Halt::UnimplementedAccess {
+                pc, address, write, ..
+            } => (*pc, *address, *write),
+            other => panic!("{other}"),
+        })
+        .collect();
+    assert_eq!(accesses, [(0x003, 0x200, false), (0x007, 0xFFFF, true)]);
+
+    let mut core = loaded(BEYOND, UnimplementedAccessPolicy::Stop);
+    for _ in 0..4 {
+        core.step();
+    }
+    assert_eq!(
+        core.halted(),
+        Some(&Halt::UnimplementedAccess {
+            pc: 0x003,
+            instruction: Instruction::MOVF {
+                reg: 0x0E,
+                dest: false
+            },
+            address: 0x200,
+            write: false,
+        })
+    );
+    assert_eq!(core.pc, 0x003);
+}
+
+#[test]
+fn host_accesses_neither_log_nor_halt() {
+    for policy in [
+        UnimplementedAccessPolicy::Warn,
+        UnimplementedAccessPolicy::Stop,
+    ] {
+        let mut core = loaded(SOURCE, policy);
+        assert_eq!(core.read(0x09), 0);
+        core.write(0x09, 0x55);
+        assert_eq!(core.read(0x200), 0);
+        core.write(0xFFFF, 0x55);
+        assert!(core.halted().is_none(), "{policy:?}");
+        assert!(core.warnings().is_empty(), "{policy:?}");
+
+        // Firmware accesses still follow the policy.
+        core.step();
+        core.step();
+        assert_eq!(
+            core.halted().is_some(),
+            policy == UnimplementedAccessPolicy::Stop
+        );
+        assert_eq!(
+            core.warnings().len(),
+            usize::from(policy == UnimplementedAccessPolicy::Warn)
+        );
+    }
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::{assemble_core, run_until};
+use p16core_sim::{Image, P16Core};
+
+const TMR0: u16 = 0x01;
+const OPTION_REG: u16 = 0x81;
+
+/// OPTION_REG with the prescaler assigned to the watchdog at 1:1.
+const PSA_WDT_1_1: u8 = 0x08;
+
+/// 18 ms at 5 MIPS.
+const TIMEOUT_CYCLES: u64 = 90_000;
+
+#[test]
+fn timeout_resets_the_core_with_to_clear() {
+    let mut core = P16Core::default();
+    assert!(
+        core.watchdog_enabled(),
+        "WDTE is set in an erased config word"
+    );
+    core.write(OPTION_REG, PSA_WDT_1_1);
+
+    run_until(&mut core, TIMEOUT_CYCLES - 1);
+    assert!(core.status.to);
+    core.step();
+    assert!(!core.status.to);
+    assert!(core.status.pd);
+    assert_eq!(core.read(OPTION_REG), 0xFF, "OPTION_REG is reset");
+}
+
+#[test]
+fn prescaler_on_the_watchdog_stretches_the_timeout() {
+    let mut core = P16Core::default();
+    // PSA set, PS = 1:4. TMR0 then counts every cycle.
+    core.write(OPTION_REG, 0x0A);
+
+    run_until(&mut core, 10);
+    assert_eq!(core.read(TMR0), 10);
+
+    run_until(&mut core, 4 * TIMEOUT_CYCLES - 1);
+    assert!(core.status.to);
+    core.step();
+    assert!(!core.status.to);
+}
+
+#[test]
+fn prescaler_on_tmr0_leaves_the_watchdog_at_1_1() {
+    let mut core = P16Core::default();
+    // PSA clear, PS = 1:2 for TMR0.
+    core.write(OPTION_REG, 0x00);
+
+    run_until(&mut core, 10);
+    assert_eq!(core.read(TMR0), 5);
+
+    run_until(&mut core, TIMEOUT_CYCLES - 1);
+    assert!(core.status.to);
+    core.step();
+    assert!(!core.status.to);
+}
+
+#[test]
+fn timeout_follows_the_clock() {
+    let mut core = P16Core::default();
+    core.set_clock_hz(4_000_000);
+    core.write(OPTION_REG, PSA_WDT_1_1);
+
+    run_until(&mut core, 18_000 - 1);
+    assert!(core.status.to);
+    core.step();
+    assert!(!core.status.to);
+}
+
+#[test]
+#[should_panic(expected = "non-zero")]
+fn zero_clock_is_rejected() {
+    P16Core::default().set_clock_hz(0);
+}
+
+#[test]
+fn clrwdt_sets_to_and_pd_and_restarts_the_period() {
+    let source = "  #include p16core.inc
+  org 0
+loop
+  clrwdt
+  goto loop
+  end
+";
+    let mut core = assemble_core(source);
+    core.write(OPTION_REG, PSA_WDT_1_1);
+    core.status.to = false;
+    core.status.pd = false;
+
+    core.step();
+    assert!(core.status.to);
+    assert!(core.status.pd);
+
+    run_until(&mut core, 3 * TIMEOUT_CYCLES);
+    assert!(core.status.to, "the watchdog never timed out");
+    assert_eq!(core.read(OPTION_REG), PSA_WDT_1_1);
+}
+
+#[test]
+fn watchdog_can_be_disabled_or_forced_on() {
+    // WDTE clear.
+    let mut core = P16Core::from_image(Image {
+        config: 0x3FFB,
+        ..Default::default()
+    });
+    assert!(!core.watchdog_enabled());
+    core.write(OPTION_REG, PSA_WDT_1_1);
+    run_until(&mut core, 2 * TIMEOUT_CYCLES);
+    assert!(core.status.to);
+
+    core.wdt_override = Some(true);
+    assert!(core.watchdog_enabled());
+    run_until(&mut core, 4 * TIMEOUT_CYCLES);
+    assert!(!core.status.to);
+
+    let mut core = P16Core::default();
+    core.wdt_override = Some(false);
+    core.write(OPTION_REG, PSA_WDT_1_1);
+    run_until(&mut core, 2 * TIMEOUT_CYCLES);
+    assert!(core.status.to);
+}